@@ -0,0 +1,353 @@
+use anyhow::anyhow;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::model::{Recipe, StagePartial};
+use crate::schedule::Schedule;
+use crate::when::duration_iso8601;
+
+const MINUTES_IN_DAY: i64 = 24 * 60;
+const SECONDS_IN_DAY: i64 = 24 * 60 * 60;
+/// Content lines longer than this many octets are folded.
+const LINE_OCTETS: usize = 75;
+
+/// The day the calendars published with the site serve on. Recipe pages move
+/// the events to the day and time picked by the reader when the calendar is
+/// downloaded, so the published files are the same from one build to the next.
+pub const PUBLISHED_DATE: Date = Date {
+    year: 2000,
+    month: 1,
+    day: 1,
+};
+
+/// A day of the proleptic Gregorian calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// The current day in UTC.
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        Date::from_days(seconds.div_euclid(SECONDS_IN_DAY))
+    }
+
+    /// The day a number of days after 1970-01-01.
+    fn from_days(days: i64) -> Self {
+        // Howard Hinnant's civil_from_days.
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        Date {
+            year: yoe + era * 400 + i64::from(month <= 2),
+            month,
+            day,
+        }
+    }
+
+    /// The number of days after 1970-01-01.
+    fn days(&self) -> i64 {
+        let year = self.year - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5
+            + i64::from(self.day)
+            - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146097 + doe - 719468
+    }
+
+    fn add_days(&self, days: i64) -> Self {
+        Date::from_days(self.days() + days)
+    }
+}
+
+impl FromStr for Date {
+    type Err = anyhow::Error;
+
+    /// Parse a date such as `2021-11-25`.
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow!("invalid date {}, expected YYYY-MM-DD", src);
+        let mut parts = src.trim().splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let (year, month, day) = (next()?, next()?, next()?);
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return Err(invalid());
+        }
+        let date = Date {
+            year: year.parse().map_err(|_| invalid())?,
+            month: month.parse().map_err(|_| invalid())?,
+            day: day.parse().map_err(|_| invalid())?,
+        };
+        // Days past the end of the month roll over into the next one.
+        if !(1..=12).contains(&date.month) || Date::from_days(date.days()) != date {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// A local date and time without a time zone, such as `20211125T173000`.
+/// Calendar apps place floating times in the time zone of the reader.
+fn floating_time(date: Date, minutes: i64) -> String {
+    let date = date.add_days(minutes.div_euclid(MINUTES_IN_DAY));
+    let minutes = minutes.rem_euclid(MINUTES_IN_DAY);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}00",
+        date.year,
+        date.month,
+        date.day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+/// A UTC date and time, such as `20211125T173000Z`.
+fn utc_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let date = Date::from_days(seconds.div_euclid(SECONDS_IN_DAY));
+    let seconds = seconds.rem_euclid(SECONDS_IN_DAY);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        date.year,
+        date.month,
+        date.day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Escape a TEXT property value.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write a content line, folding it into lines of at most 75 octets without
+/// splitting characters.
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > LINE_OCTETS {
+            ics.push_str("\r\n ");
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn push_alarm(ics: &mut String, trigger: Duration, description: &str) {
+    push_line(ics, "BEGIN:VALARM");
+    push_line(ics, "ACTION:DISPLAY");
+    push_line(ics, &format!("TRIGGER:{}", duration_iso8601(trigger)));
+    push_line(ics, &format!("DESCRIPTION:{}", escape_text(description)));
+    push_line(ics, "END:VALARM");
+}
+
+/// An iCalendar file with an event for each stage of a recipe to serve it at
+/// a minute of a day. Each event alarms when the stage starts and when the
+/// timer of each timed step runs out, counting the timed steps of the stage
+/// one after the other. Hands-off stages do not mark the cook as busy.
+///
+/// The stages are the localized stages of the recipe, in the same order.
+pub fn schedule_ics(
+    recipe: &Recipe,
+    name: &str,
+    stages: &[StagePartial],
+    serve_on: Date,
+    serve_at: i64,
+    recipe_url: Option<&str>,
+    stamp: SystemTime,
+) -> Result<String, anyhow::Error> {
+    let schedule = Schedule::new(recipe)?;
+    let serve = floating_time(serve_on, serve_at);
+    let stamp = utc_time(stamp);
+
+    let mut ics = String::new();
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//jrb//Cooking schedule//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "METHOD:PUBLISH");
+    push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape_text(name)));
+
+    for (scheduled, start) in schedule.stages.iter().zip(schedule.start_minutes(serve_at)) {
+        let stage = stages
+            .get(scheduled.index)
+            .ok_or_else(|| anyhow!("no localized stage {}", scheduled.anchor))?;
+        let minutes = ((scheduled.end - scheduled.start).as_secs() as i64 + 59) / 60;
+
+        push_line(&mut ics, "BEGIN:VEVENT");
+        push_line(
+            &mut ics,
+            &format!("UID:{}-{}-{}@jrb", recipe.id, scheduled.anchor, serve),
+        );
+        push_line(&mut ics, &format!("DTSTAMP:{}", stamp));
+        push_line(
+            &mut ics,
+            &format!("DTSTART:{}", floating_time(serve_on, start)),
+        );
+        // An event without an end lasts no time at all.
+        if minutes > 0 {
+            push_line(
+                &mut ics,
+                &format!("DTEND:{}", floating_time(serve_on, start + minutes)),
+            );
+        }
+        push_line(
+            &mut ics,
+            &format!(
+                "SUMMARY:{}",
+                escape_text(&format!("{}: {}", name, stage.name))
+            ),
+        );
+        let description: Vec<&str> = stage.steps.iter().map(|step| step.text.as_str()).collect();
+        if !description.is_empty() {
+            push_line(
+                &mut ics,
+                &format!("DESCRIPTION:{}", escape_text(&description.join("\n"))),
+            );
+        }
+        if let Some(recipe_url) = recipe_url {
+            push_line(&mut ics, &format!("URL:{}#{}", recipe_url, stage.id));
+        }
+        if scheduled.hands_off {
+            push_line(&mut ics, "TRANSP:TRANSPARENT");
+        }
+
+        push_alarm(&mut ics, Duration::ZERO, &stage.name);
+        let mut timer = Duration::ZERO;
+        for step in &stage.steps {
+            if let Some(seconds) = step.timer_seconds {
+                timer += Duration::from_secs(seconds);
+                push_alarm(&mut ics, timer, &step.text);
+            }
+        }
+        push_line(&mut ics, "END:VEVENT");
+    }
+
+    push_line(&mut ics, "END:VCALENDAR");
+    Ok(ics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Stage, Step};
+
+    #[test]
+    fn date_ok() {
+        let date: Date = "2021-11-25".parse().unwrap();
+        assert_eq!(date.to_string(), "2021-11-25");
+        assert_eq!(Date::from_days(0).to_string(), "1970-01-01");
+        assert_eq!(date.add_days(7).to_string(), "2021-12-02");
+        assert_eq!(date.add_days(-331).to_string(), "2020-12-29");
+        assert_eq!(
+            "2024-02-28"
+                .parse::<Date>()
+                .unwrap()
+                .add_days(1)
+                .to_string(),
+            "2024-02-29"
+        );
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2021-13-01".parse::<Date>().is_err());
+        assert!("25/11/2021".parse::<Date>().is_err());
+        assert_eq!(floating_time(date, -30), "20211124T233000");
+        assert_eq!(
+            utc_time(UNIX_EPOCH + Duration::from_secs(90061)),
+            "19700102T010101Z"
+        );
+    }
+
+    #[test]
+    fn schedule_ics_ok() {
+        let mut recipe = Recipe::init(None, None, false);
+        let mut prep = Stage::init(String::from("Prep"));
+        prep.prep_time = Some(Duration::from_secs(20 * 60));
+        prep.steps = vec![Step::new("Chop the onions, finely.")];
+        let mut cook = Stage::init(String::from("Cook"));
+        cook.cook_time = Some(Duration::from_secs(30 * 60));
+        cook.hands_off = true;
+        let mut simmer = Step::new("Simmer");
+        simmer.duration = Some("15m-20m".parse().unwrap());
+        cook.steps = vec![simmer];
+        recipe.stages = vec![prep, cook];
+
+        let stages: Vec<StagePartial> = recipe
+            .stages
+            .iter()
+            .enumerate()
+            .map(|(index, stage)| stage.to_partial(index, None).unwrap())
+            .collect();
+        let ics = schedule_ics(
+            &recipe,
+            "Chili",
+            &stages,
+            "2021-11-25".parse().unwrap(),
+            parse_clock("00:30"),
+            Some("https://example.com/en_US/chili/"),
+            UNIX_EPOCH,
+        )
+        .unwrap();
+
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= LINE_OCTETS + 1));
+        assert!(ics.contains("DTSTAMP:19700101T000000Z\r\n"));
+        assert!(ics.contains("DTSTART:20211124T234000\r\nDTEND:20211125T000000\r\n"));
+        assert!(ics.contains("SUMMARY:Chili: Prep\r\nDESCRIPTION:Chop the onions\\, finely.\r\n"));
+        assert!(
+            ics.contains("URL:https://example.com/en_US/chili/#stage-2\r\nTRANSP:TRANSPARENT\r\n")
+        );
+        assert!(ics.contains("TRIGGER:PT15M\r\nDESCRIPTION:Simmer\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert_eq!(ics.matches("BEGIN:VALARM").count(), 3);
+
+        let mut folded = String::new();
+        push_line(&mut folded, &"é".repeat(40));
+        assert_eq!(
+            folded,
+            format!("{}\r\n {}\r\n", "é".repeat(37), "é".repeat(3))
+        );
+    }
+
+    fn parse_clock(src: &str) -> i64 {
+        crate::schedule::parse_clock(src).unwrap()
+    }
+}
@@ -0,0 +1,183 @@
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::locale::{fallback_chain, lookup, lookup_or_default, plural_category, PluralRule};
+
+/// The directory, relative to the templates directory, holding one message
+/// catalog per locale named `<locale>.yml`.
+pub const MESSAGES_DIR: &str = "messages";
+
+/// A translated message, either a single string or one string per CLDR plural
+/// category (`zero`, `one`, `two`, `few`, `many` and `other`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Message {
+    Text(String),
+    Plural(HashMap<String, String>),
+}
+
+/// Site UI strings for every locale. Messages use `{name}` placeholders for
+/// interpolated values and `{count}` for the count of plural messages.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Catalog {
+    pub messages: HashMap<String, HashMap<String, Message>>,
+}
+
+impl Catalog {
+    pub fn load(templates_dir: &Path) -> Result<Self, anyhow::Error> {
+        let mut catalog = Catalog::default();
+
+        let messages_dir = templates_dir.join(MESSAGES_DIR);
+        if !messages_dir.is_dir() {
+            return Ok(catalog);
+        }
+
+        for entry in fs::read_dir(&messages_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("yml") {
+                continue;
+            }
+            let locale = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_string();
+            let messages_yaml = fs::read_to_string(&path)?;
+            let messages: HashMap<String, Message> = serde_yaml::from_str(&messages_yaml)
+                .map_err(|err| anyhow::anyhow!("{}: {}", path.display(), err))?;
+            catalog.messages.insert(locale, messages);
+        }
+
+        Ok(catalog)
+    }
+
+    /// Find a message for the locale, following the locale fallback chain.
+    pub fn message(&self, locale: &str, key: &str) -> Option<(&str, &Message)> {
+        fallback_chain(locale).into_iter().find_map(|candidate| {
+            self.messages
+                .get_key_value(candidate)
+                .and_then(|(found, messages)| {
+                    messages.get(key).map(|message| (found.as_str(), message))
+                })
+        })
+    }
+
+    /// Translate a message, replacing `{name}` placeholders with the given
+    /// arguments. Unknown keys are returned unchanged.
+    pub fn translate(&self, locale: &str, key: &str, args: &[(&str, String)]) -> String {
+        self.translate_count(locale, key, None, args)
+    }
+
+    /// Translate a message, picking the plural form for the count when the
+    /// message has plural forms.
+    pub fn translate_count(
+        &self,
+        locale: &str,
+        key: &str,
+        count: Option<f64>,
+        args: &[(&str, String)],
+    ) -> String {
+        let (found_locale, message) = match self.message(locale, key) {
+            Some(found) => found,
+            None => {
+                warn!("missing message {} for {}", key, locale);
+                return key.to_string();
+            }
+        };
+
+        let template = match message {
+            Message::Text(text) => text.clone(),
+            Message::Plural(forms) => {
+                let rule = lookup(found_locale)
+                    .map(|registered| registered.plurals)
+                    .unwrap_or(PluralRule::OneOther);
+                let category = plural_category(rule, count.unwrap_or_default());
+                forms
+                    .get(category)
+                    .or_else(|| forms.get("other"))
+                    .cloned()
+                    .unwrap_or_else(|| key.to_string())
+            }
+        };
+
+        let mut translated = template;
+        if let Some(count) = count {
+            translated =
+                translated.replace("{count}", &lookup_or_default(locale).format_number(count));
+        }
+        for (name, value) in args {
+            translated = translated.replace(&format!("{{{}}}", name), value);
+        }
+        translated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Catalog {
+        let en_us: HashMap<String, Message> = serde_yaml::from_str(
+            "
+greeting: Hello {name}
+steps:
+  one: '{count} step'
+  other: '{count} steps'
+",
+        )
+        .unwrap();
+        let fr_fr: HashMap<String, Message> = serde_yaml::from_str(
+            "
+steps:
+  one: '{count} étape'
+  other: '{count} étapes'
+",
+        )
+        .unwrap();
+        Catalog {
+            messages: HashMap::from([
+                (String::from("en_US"), en_us),
+                (String::from("fr_FR"), fr_fr),
+            ]),
+        }
+    }
+
+    #[test]
+    fn translate_ok() {
+        let catalog = catalog();
+        assert_eq!(
+            catalog.translate("en_US", "greeting", &[("name", String::from("Nick"))]),
+            "Hello Nick"
+        );
+        assert_eq!(
+            catalog.translate("fr_CA", "greeting", &[("name", String::from("Nick"))]),
+            "Hello Nick"
+        );
+        assert_eq!(catalog.translate("en_US", "missing", &[]), "missing");
+    }
+
+    #[test]
+    fn translate_count_ok() {
+        let catalog = catalog();
+        assert_eq!(
+            catalog.translate_count("en_US", "steps", Some(1.0), &[]),
+            "1 step"
+        );
+        assert_eq!(
+            catalog.translate_count("en_US", "steps", Some(0.0), &[]),
+            "0 steps"
+        );
+        assert_eq!(
+            catalog.translate_count("fr_CA", "steps", Some(0.0), &[]),
+            "0 étape"
+        );
+        assert_eq!(
+            catalog.translate_count("fr_FR", "steps", Some(3.0), &[]),
+            "3 étapes"
+        );
+        assert_eq!(
+            catalog.translate_count("fr_FR", "steps", Some(1.5), &[]),
+            "1,5 étape"
+        );
+    }
+}
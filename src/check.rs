@@ -0,0 +1,490 @@
+use serde_json::Value;
+use std::{fs, path::Path};
+use url::Url;
+
+use crate::when::parse_duration_iso8601;
+
+/// Properties of a schema.org Recipe that search engines require.
+const REQUIRED_PROPERTIES: &[&str] = &["name", "image"];
+
+/// Properties of a schema.org Recipe that search engines recommend. There is
+/// no `aggregateRating` as the site does not collect ratings.
+const RECOMMENDED_PROPERTIES: &[&str] = &[
+    "author",
+    "datePublished",
+    "description",
+    "keywords",
+    "recipeCategory",
+    "recipeCuisine",
+    "recipeIngredient",
+    "recipeInstructions",
+    "recipeYield",
+    "prepTime",
+    "cookTime",
+    "totalTime",
+    "nutrition",
+    "video",
+];
+
+const DURATION_PROPERTIES: &[&str] = &["prepTime", "cookTime", "totalTime"];
+
+/// The structured data problems found in a generated page. Errors make the
+/// recipe ineligible for rich results, warnings are recommended properties
+/// that are missing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageReport {
+    pub page: String,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Check the JSON-LD and microdata recipes of every page in the public
+/// directory. Pages without a recipe are not reported.
+pub fn check_structured_data(public_dir: &Path) -> Result<Vec<PageReport>, anyhow::Error> {
+    let mut pages: Vec<String> = walkdir::WalkDir::new(public_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| entry.path().extension().and_then(|ext| ext.to_str()) == Some("html"))
+        .filter_map(|entry| {
+            entry
+                .path()
+                .strip_prefix(public_dir)
+                .ok()
+                .and_then(|path| path.to_str())
+                .map(|path| path.to_string())
+        })
+        .collect();
+    pages.sort();
+
+    let mut reports: Vec<PageReport> = Vec::new();
+    for page in pages {
+        let html = fs::read_to_string(public_dir.join(&page))?;
+        if let Some(report) = check_page(&page, &html) {
+            reports.push(report);
+        }
+    }
+    Ok(reports)
+}
+
+/// Check the recipes published in a page, or `None` when it has none.
+pub fn check_page(page: &str, html: &str) -> Option<PageReport> {
+    let mut report = PageReport {
+        page: page.to_string(),
+        ..PageReport::default()
+    };
+    let mut found = false;
+
+    for (index, json_ld) in json_ld_blocks(html).iter().enumerate() {
+        let document: Value = match serde_json::from_str(json_ld) {
+            Ok(document) => document,
+            Err(err) => {
+                found = true;
+                report
+                    .errors
+                    .push(format!("JSON-LD block {} is invalid: {}", index + 1, err));
+                continue;
+            }
+        };
+        for recipe in recipes(&document) {
+            found = true;
+            check_recipe(recipe, &mut report);
+        }
+    }
+
+    if let Some(properties) = microdata_recipe(html) {
+        found = true;
+        check_microdata(&properties, &mut report);
+    }
+
+    match found {
+        true => Some(report),
+        false => None,
+    }
+}
+
+fn check_recipe(recipe: &Value, report: &mut PageReport) {
+    let present = |property: &str| match recipe.get(property) {
+        None | Some(Value::Null) => false,
+        Some(Value::String(value)) => !value.trim().is_empty(),
+        Some(Value::Array(values)) => !values.is_empty(),
+        Some(_) => true,
+    };
+
+    for property in REQUIRED_PROPERTIES {
+        if !present(property) {
+            report.errors.push(format!("missing {}", property));
+        }
+    }
+    for property in RECOMMENDED_PROPERTIES {
+        if !present(property) {
+            report
+                .warnings
+                .push(format!("missing recommended {}", property));
+        }
+    }
+
+    for image in images(recipe.get("image")) {
+        if !is_absolute_url(&image) {
+            report
+                .errors
+                .push(format!("image {} is not an absolute URL", image));
+        }
+    }
+
+    for property in DURATION_PROPERTIES {
+        if let Some(value) = recipe.get(*property) {
+            check_duration(property, value, report);
+        }
+    }
+    if present("prepTime") != present("cookTime") {
+        report.warnings.push(String::from(
+            "prepTime and cookTime should be given together",
+        ));
+    }
+
+    if let Some(ingredients) = recipe.get("recipeIngredient") {
+        let valid = match ingredients {
+            Value::Array(values) => values.iter().all(|value| value.is_string()),
+            _ => false,
+        };
+        if !valid {
+            report
+                .errors
+                .push(String::from("recipeIngredient is not a list of text"));
+        }
+    }
+
+    if let Some(instructions) = recipe.get("recipeInstructions") {
+        check_instructions(instructions, report);
+    }
+
+    if let Some(nutrition) = recipe.get("nutrition") {
+        if nutrition.get("calories").is_none() {
+            report
+                .warnings
+                .push(String::from("missing recommended nutrition.calories"));
+        }
+    }
+
+    if let Some(video) = recipe.get("video") {
+        for property in ["name", "thumbnailUrl", "uploadDate"] {
+            if video.get(property).is_none() {
+                report.errors.push(format!("missing video.{}", property));
+            }
+        }
+        if let Some(duration) = video.get("duration") {
+            check_duration("video.duration", duration, report);
+        }
+    }
+}
+
+fn check_instructions(instructions: &Value, report: &mut PageReport) {
+    let elements: Vec<&Value> = match instructions {
+        Value::Array(values) => values.iter().collect(),
+        Value::String(_) => return,
+        value => vec![value],
+    };
+
+    for element in elements {
+        match element.get("@type").and_then(|value| value.as_str()) {
+            Some("HowToSection") => {
+                let name = element
+                    .get("name")
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default();
+                match element.get("itemListElement") {
+                    Some(Value::Array(steps)) if !steps.is_empty() => {
+                        for step in steps {
+                            check_step(step, report);
+                        }
+                    }
+                    _ => report
+                        .errors
+                        .push(format!("HowToSection {} has no steps", name)),
+                }
+            }
+            _ => check_step(element, report),
+        }
+    }
+}
+
+fn check_step(step: &Value, report: &mut PageReport) {
+    if step.is_string() {
+        return;
+    }
+    match step.get("text").and_then(|value| value.as_str()) {
+        Some(text) if !text.trim().is_empty() => {}
+        _ => report.errors.push(String::from("HowToStep has no text")),
+    }
+    if let Some(url) = step.get("url").and_then(|value| value.as_str()) {
+        if !is_absolute_url(url) {
+            report
+                .errors
+                .push(format!("HowToStep url {} is not an absolute URL", url));
+        }
+    }
+}
+
+fn check_duration(property: &str, value: &Value, report: &mut PageReport) {
+    let valid = value.as_str().and_then(parse_duration_iso8601).is_some();
+    if !valid {
+        report.errors.push(format!(
+            "{} {} is not an ISO 8601 duration",
+            property, value
+        ));
+    }
+}
+
+/// Check a recipe published as microdata, given its item properties and
+/// their values.
+fn check_microdata(properties: &[(String, String)], report: &mut PageReport) {
+    let present = |property: &str| properties.iter().any(|(name, _)| name == property);
+
+    for property in REQUIRED_PROPERTIES {
+        if !present(property) {
+            report
+                .errors
+                .push(format!("microdata missing {}", property));
+        }
+    }
+    for property in RECOMMENDED_PROPERTIES {
+        if !present(property) {
+            report
+                .warnings
+                .push(format!("microdata missing recommended {}", property));
+        }
+    }
+    for (name, value) in properties {
+        if DURATION_PROPERTIES.contains(&name.as_str()) && parse_duration_iso8601(value).is_none() {
+            report.errors.push(format!(
+                "microdata {} {} is not an ISO 8601 duration",
+                name, value
+            ));
+        }
+    }
+}
+
+/// The Recipe objects of a JSON-LD document, which may be a single object, a
+/// list of objects or a `@graph`.
+fn recipes(document: &Value) -> Vec<&Value> {
+    match document {
+        Value::Array(values) => values.iter().flat_map(recipes).collect(),
+        Value::Object(object) => match object.get("@graph") {
+            Some(graph) => recipes(graph),
+            None => {
+                let is_recipe = match object.get("@type") {
+                    Some(Value::String(schema_type)) => schema_type == "Recipe",
+                    Some(Value::Array(schema_types)) => schema_types
+                        .iter()
+                        .any(|schema_type| schema_type == "Recipe"),
+                    _ => false,
+                };
+                match is_recipe {
+                    true => vec![document],
+                    false => Vec::new(),
+                }
+            }
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// The URLs of an `image` property, which may be a URL, an ImageObject or a
+/// list of either.
+fn images(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(url)) => vec![url.clone()],
+        Some(Value::Array(values)) => values
+            .iter()
+            .flat_map(|value| images(Some(value)))
+            .collect(),
+        Some(Value::Object(object)) => images(object.get("url")),
+        _ => Vec::new(),
+    }
+}
+
+fn is_absolute_url(src: &str) -> bool {
+    match Url::parse(src) {
+        Ok(url) => (url.scheme() == "https" || url.scheme() == "http") && url.has_host(),
+        Err(_) => false,
+    }
+}
+
+/// The contents of the `application/ld+json` script elements of a page.
+fn json_ld_blocks(html: &str) -> Vec<&str> {
+    let mut blocks: Vec<&str> = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find("<script") {
+        let tag_end = match rest[start..].find('>') {
+            Some(offset) => start + offset + 1,
+            None => break,
+        };
+        let content_end = match rest[tag_end..].find("</script>") {
+            Some(offset) => tag_end + offset,
+            None => break,
+        };
+        if attribute(&rest[start..tag_end], "type").as_deref() == Some("application/ld+json") {
+            blocks.push(&rest[tag_end..content_end]);
+        }
+        rest = &rest[content_end..];
+    }
+    blocks
+}
+
+/// The item properties of the first schema.org Recipe item in a page. The
+/// value of a property is its `content`, `datetime`, `src` or `href`
+/// attribute.
+fn microdata_recipe(html: &str) -> Option<Vec<(String, String)>> {
+    let tags: Vec<&str> = html
+        .split('<')
+        .filter_map(|tag| tag.split_once('>').map(|(tag, _)| tag))
+        .collect();
+    let start = tags.iter().position(|tag| {
+        attribute(tag, "itemtype")
+            .map(|item_type| {
+                item_type
+                    .trim_end_matches('/')
+                    .ends_with("schema.org/Recipe")
+            })
+            .unwrap_or(false)
+    })?;
+
+    let mut properties: Vec<(String, String)> = Vec::new();
+    for tag in &tags[start..] {
+        if let Some(names) = attribute(tag, "itemprop") {
+            let value = ["content", "datetime", "src", "href"]
+                .iter()
+                .find_map(|name| attribute(tag, name))
+                .unwrap_or_default();
+            for name in names.split_whitespace() {
+                properties.push((name.to_string(), value.clone()));
+            }
+        }
+    }
+    Some(properties)
+}
+
+/// The value of a quoted attribute of an HTML start tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(position) = rest.find(name) {
+        let preceded = rest[..position]
+            .chars()
+            .last()
+            .map(|c| c.is_whitespace())
+            .unwrap_or(false);
+        let after = rest[position + name.len()..].trim_start();
+        rest = &rest[position + name.len()..];
+        if !preceded {
+            continue;
+        }
+        if let Some(value) = after.strip_prefix('=') {
+            let value = value.trim_start();
+            let quote = value.chars().next()?;
+            if quote == '"' || quote == '\'' {
+                let value = &value[1..];
+                return value.find(quote).map(|end| value[..end].to_string());
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_page_ok() {
+        let html = r#"<html><head>
+<script type="text/javascript">var a = "<b>";</script>
+<script type="application/ld+json">
+{
+  "@context": "https://schema.org",
+  "@type": "Recipe",
+  "name": "Pie",
+  "image": ["https://example.com/media/pie.jpg"],
+  "author": [{"@type": "Person", "name": "Jo"}],
+  "datePublished": "2021-10-01",
+  "description": "A pie.",
+  "keywords": "pie",
+  "recipeCategory": "Dessert",
+  "recipeCuisine": "American",
+  "recipeYield": "8",
+  "prepTime": "PT30M",
+  "cookTime": "PT1H",
+  "totalTime": "PT1H30M",
+  "recipeIngredient": ["1 crust"],
+  "recipeInstructions": [{
+    "@type": "HowToSection",
+    "name": "Bake",
+    "itemListElement": [{"@type": "HowToStep", "text": "Bake it.", "url": "https://example.com/pie/#a"}]
+  }],
+  "nutrition": {"@type": "NutritionInformation", "calories": "240 calories"},
+  "video": {"@type": "VideoObject", "name": "Pie", "thumbnailUrl": "https://example.com/t.jpg", "uploadDate": "2021-10-01", "duration": "PT5M"}
+}
+</script></head></html>"#;
+        assert_eq!(
+            check_page("pie/index.html", html),
+            Some(PageReport {
+                page: String::from("pie/index.html"),
+                errors: Vec::new(),
+                warnings: Vec::new(),
+            })
+        );
+        assert_eq!(check_page("index.html", "<html></html>"), None);
+    }
+
+    #[test]
+    fn check_page_err() {
+        let html = r#"<script type="application/ld+json">
+{"@context": "https://schema.org", "@graph": [{"@type": "WebSite"}, {
+  "@type": "Recipe",
+  "name": "Pie",
+  "image": "/media/pie.jpg",
+  "prepTime": "P5M",
+  "recipeInstructions": [{"@type": "HowToStep", "text": ""}]
+}]}
+</script>"#;
+        let report = check_page("pie/index.html", html).unwrap();
+        assert_eq!(
+            report.errors,
+            vec![
+                "image /media/pie.jpg is not an absolute URL",
+                "prepTime \"P5M\" is not an ISO 8601 duration",
+                "HowToStep has no text",
+            ]
+        );
+        assert!(report
+            .warnings
+            .contains(&String::from("missing recommended recipeIngredient")));
+        assert!(report.warnings.contains(&String::from(
+            "prepTime and cookTime should be given together"
+        )));
+
+        let report = check_page(
+            "pie/index.html",
+            r#"<script type="application/ld+json">{"@type": "Recipe",</script>"#,
+        )
+        .unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("JSON-LD block 1 is invalid"));
+    }
+
+    #[test]
+    fn check_microdata_err() {
+        let html = r#"<div itemscope itemtype="https://schema.org/Recipe">
+<h1 itemprop="name">Pie</h1>
+<meta itemprop="cookTime" content="1 hour">
+</div>"#;
+        let report = check_page("pie/index.html", html).unwrap();
+        assert_eq!(
+            report.errors,
+            vec![
+                "microdata missing image",
+                "microdata cookTime 1 hour is not an ISO 8601 duration",
+            ]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, path::PathBuf};
+
+use crate::model::US_ENGLISH;
+use crate::nutrition::NUTRIENTS_FILE;
+use crate::schedule::parse_clock;
+use crate::taxonomy::TAXONOMY_FILE;
+
+/// The default project configuration file, relative to the working directory.
+pub const CONFIG_FILE: &str = "jrb.toml";
+
+/// The project configuration read from `jrb.toml`. Every section is optional
+/// and command line options take precedence over it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub site: SiteConfig,
+    pub locales: LocalesConfig,
+    pub dirs: DirsConfig,
+    pub features: Features,
+    pub calendar: CalendarConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiteConfig {
+    pub name: String,
+    /// The base URL for the generated site.
+    pub public_url: Option<String>,
+    /// The URL of the source of the site, linked from the footer.
+    pub repository: Option<String>,
+    pub authors: Vec<Author>,
+    pub social: Social,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Author {
+    pub name: String,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Social {
+    /// The Twitter handle of the site, including the leading `@`.
+    pub twitter: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocalesConfig {
+    /// The locale of the site root and of the `x-default` alternate.
+    pub default: String,
+    /// The locales the site is published in.
+    pub enabled: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DirsConfig {
+    pub recipes: PathBuf,
+    pub menus: PathBuf,
+    pub templates: PathBuf,
+    #[serde(rename = "static")]
+    pub static_files: PathBuf,
+    pub public: PathBuf,
+    pub taxonomy_file: PathBuf,
+    pub nutrients_file: PathBuf,
+}
+
+/// Optional parts of the generated site.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    pub search: bool,
+    pub oembed: bool,
+    pub letter_index: bool,
+    pub tag_pages: bool,
+    /// A cooking schedule calendar for each recipe with more than one stage.
+    pub calendar: bool,
+    /// Pages for the menus in the menus directory.
+    pub menus: bool,
+    /// Nutrition facts computed from the nutrient table.
+    pub nutrition: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalendarConfig {
+    /// The time the calendars generated with the site serve at, such as
+    /// `18:30`.
+    pub serve_at: String,
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        SiteConfig {
+            name: String::from("jrb"),
+            public_url: None,
+            repository: None,
+            authors: Vec::new(),
+            social: Social::default(),
+        }
+    }
+}
+
+impl Default for LocalesConfig {
+    fn default() -> Self {
+        LocalesConfig {
+            default: US_ENGLISH.to_string(),
+            enabled: vec![US_ENGLISH.to_string()],
+        }
+    }
+}
+
+impl Default for DirsConfig {
+    fn default() -> Self {
+        DirsConfig {
+            recipes: PathBuf::from("recipes"),
+            menus: PathBuf::from("menus"),
+            templates: PathBuf::from("templates"),
+            static_files: PathBuf::from("static"),
+            public: PathBuf::from("public"),
+            taxonomy_file: PathBuf::from(TAXONOMY_FILE),
+            nutrients_file: PathBuf::from(NUTRIENTS_FILE),
+        }
+    }
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Features {
+            search: true,
+            oembed: true,
+            letter_index: true,
+            tag_pages: true,
+            calendar: true,
+            menus: true,
+            nutrition: true,
+        }
+    }
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig {
+            serve_at: String::from("18:00"),
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration file, or the defaults when the file does not
+    /// exist.
+    pub fn load(config_file: &Path) -> Result<Self, anyhow::Error> {
+        if !config_file.exists() {
+            return Ok(Config::default());
+        }
+        let config_toml = fs::read_to_string(config_file)
+            .map_err(|err| anyhow!("{}: {}", config_file.display(), err))?;
+        let config: Config = toml::from_str(&config_toml)
+            .map_err(|err| anyhow!("{}: {}", config_file.display(), err))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.locales.enabled.is_empty() {
+            return Err(anyhow!("locales.enabled cannot be empty"));
+        }
+        if !self.locales.enabled.contains(&self.locales.default) {
+            return Err(anyhow!(
+                "locales.default {} must be one of locales.enabled",
+                self.locales.default
+            ));
+        }
+        if let Some(twitter) = &self.site.social.twitter {
+            if !twitter.starts_with('@') {
+                return Err(anyhow!("site.social.twitter must start with @"));
+            }
+        }
+        parse_clock(&self.calendar.serve_at)
+            .map_err(|err| anyhow!("calendar.serve_at: {}", err))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ok() {
+        let config: Config = toml::from_str(
+            r#"
+[site]
+name = "Example Recipes"
+authors = [{ name = "Jo", url = "https://example.com/" }]
+social = { twitter = "@example" }
+
+[locales]
+default = "fr_FR"
+enabled = ["en_US", "fr_FR"]
+
+[features]
+oembed = false
+
+[calendar]
+serve_at = "19:15"
+"#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.site.name, "Example Recipes");
+        assert_eq!(config.site.authors[0].name, "Jo");
+        assert_eq!(config.locales.default, "fr_FR");
+        assert_eq!(config.dirs.recipes, PathBuf::from("recipes"));
+        assert_eq!(config.dirs.menus, PathBuf::from("menus"));
+        assert_eq!(config.dirs.nutrients_file, PathBuf::from("nutrients.csv"));
+        assert!(!config.features.oembed);
+        assert!(config.features.search);
+        assert_eq!(config.calendar.serve_at, "19:15");
+    }
+
+    #[test]
+    fn validate_err() {
+        let mut config = Config::default();
+        config.locales.default = String::from("fr_FR");
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "locales.default fr_FR must be one of locales.enabled"
+        );
+        assert!(toml::from_str::<Config>("[site]\nnmae = \"typo\"").is_err());
+
+        let mut config = Config::default();
+        config.calendar.serve_at = String::from("7pm");
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "calendar.serve_at: invalid time 7pm, expected HH:MM"
+        );
+    }
+}
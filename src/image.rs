@@ -1,8 +1,51 @@
+use anyhow::anyhow;
+use fnv::FnvHasher;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+use rusttype::{point, Font, Scale};
+use std::collections::HashMap;
+use std::hash::Hasher;
 use std::path::Path;
+use uuid::Uuid;
+
+use crate::model::{ImageDetails, ImageManifest, RecipePartial};
+use crate::site::load_recipes;
+use crate::taxonomy::Taxonomy;
+
+const PLACEHOLDER_SIZE: u32 = 16;
+const PLACEHOLDER_QUALITY: u8 = 50;
+
+const CARD_WIDTH: u32 = 1200;
+const CARD_HEIGHT: u32 = 630;
+const CARD_MARGIN: i32 = 72;
+const CARD_QUALITY: u8 = 85;
+/// Part of the hash of every share card. Change it when the card layout
+/// changes so that every card is rendered again.
+const CARD_LAYOUT: &str = "1";
+
+const CARD_TITLE_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf");
+const CARD_BODY_FONT: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+
+/// Background colors used for share cards of recipes without a photo. The
+/// color is picked from the recipe category so that cards are consistent
+/// within a category.
+const CARD_COLORS: &[[u8; 3]; 8] = &[
+    [0x8c, 0x2f, 0x39],
+    [0x2d, 0x6a, 0x4f],
+    [0x1d, 0x35, 0x57],
+    [0xb3, 0x59, 0x1a],
+    [0x5c, 0x3d, 0x7a],
+    [0x0f, 0x5e, 0x6e],
+    [0x7a, 0x5c, 0x1e],
+    [0x45, 0x45, 0x45],
+];
 
 pub fn generate_thumbnails(recipe_dir: &Path) -> Result<(), anyhow::Error> {
+    let mut manifest = ImageManifest::load(recipe_dir)?;
+
     for entry in walkdir::WalkDir::new(recipe_dir) {
-        let entry = entry.unwrap();
+        let entry = entry?;
         let path = entry.path();
 
         let fname = path.display().to_string();
@@ -16,7 +59,7 @@ pub fn generate_thumbnails(recipe_dir: &Path) -> Result<(), anyhow::Error> {
             .to_str()
             .unwrap_or_default();
 
-        if file_stem.ends_with("_thumbnail") {
+        if file_stem.ends_with("_thumbnail") || file_stem.ends_with("_card") {
             println!("SKIP {}", path.display());
             continue;
         }
@@ -26,19 +69,314 @@ pub fn generate_thumbnails(recipe_dir: &Path) -> Result<(), anyhow::Error> {
             .unwrap()
             .join(format!("{}_thumbnail.jpg", file_stem));
 
-        if thumbnail.exists() {
+        let recipe_id = Uuid::parse_str(file_stem).ok();
+        let has_details = recipe_id
+            .map(|id| manifest.images.contains_key(&id))
+            .unwrap_or(true);
+
+        if thumbnail.exists() && has_details {
             println!("SKIP {}", path.display());
             continue;
         }
 
-        println!("generating thumbnail for {}", path.display());
+        let source = image::open(path).map_err(|err| anyhow!("{}: {}", path.display(), err))?;
 
-        let source = image::open(fname).unwrap();
+        if !thumbnail.exists() {
+            println!("generating thumbnail for {}", path.display());
 
-        let thumbnail_img = image::imageops::thumbnail(&source, 200, 200);
+            let thumbnail_img = image::imageops::thumbnail(&source, 200, 200);
+
+            thumbnail_img
+                .save(&thumbnail)
+                .map_err(|err| anyhow!("{}: {}", thumbnail.display(), err))?;
+        }
+
+        if let Some(recipe_id) = recipe_id {
+            if !has_details {
+                println!("generating placeholder for {}", path.display());
+
+                manifest.images.insert(recipe_id, image_details(&source)?);
+            }
+        }
+    }
+
+    manifest.save(recipe_dir)
+}
+
+pub fn image_details(source: &DynamicImage) -> Result<ImageDetails, anyhow::Error> {
+    let (width, height) = source.dimensions();
+    Ok(ImageDetails {
+        width,
+        height,
+        color: dominant_color(source),
+        placeholder: placeholder(source)?,
+    })
+}
 
-        thumbnail_img.save(thumbnail).unwrap();
+/// Encode a tiny, low quality copy of the image as a `data:` URI that can be
+/// inlined into pages while the real image loads.
+fn placeholder(source: &DynamicImage) -> Result<String, anyhow::Error> {
+    let tiny = source
+        .thumbnail(PLACEHOLDER_SIZE, PLACEHOLDER_SIZE)
+        .to_rgb8();
+
+    let mut encoded: Vec<u8> = Vec::new();
+    JpegEncoder::new_with_quality(&mut encoded, PLACEHOLDER_QUALITY).encode_image(&tiny)?;
+
+    Ok(format!(
+        "data:image/jpeg;base64,{}",
+        base64::encode(&encoded)
+    ))
+}
+
+/// Find the most common color in the image by bucketing the pixels of a
+/// downscaled copy and averaging the largest bucket.
+fn dominant_color(source: &DynamicImage) -> String {
+    let sample = source.thumbnail(64, 64).to_rgb8();
+
+    let mut buckets: HashMap<(u8, u8, u8), (u32, [u32; 3])> = HashMap::new();
+    for pixel in sample.pixels() {
+        let [r, g, b] = pixel.0;
+        let bucket = buckets
+            .entry((r >> 5, g >> 5, b >> 5))
+            .or_insert((0, [0; 3]));
+        bucket.0 += 1;
+        bucket.1[0] += r as u32;
+        bucket.1[1] += g as u32;
+        bucket.1[2] += b as u32;
+    }
+
+    match buckets
+        .values()
+        .max_by_key(|(count, sums)| (*count, sums[0] + sums[1] + sums[2]))
+    {
+        Some((count, sums)) => format!(
+            "#{:02x}{:02x}{:02x}",
+            sums[0] / count,
+            sums[1] / count,
+            sums[2] / count
+        ),
+        None => String::from("#f8f9fa"),
+    }
+}
+
+/// Render a 1200x630 Open Graph share card for every recipe and site locale.
+/// A card is only rendered again when the text or photo it shows changed
+/// since it was last rendered.
+pub fn generate_share_cards(
+    recipe_dir: &Path,
+    taxonomy: &Taxonomy,
+    site_locales: &[String],
+) -> Result<(), anyhow::Error> {
+    let title_font = Font::try_from_bytes(CARD_TITLE_FONT)
+        .ok_or_else(|| anyhow!("unable to load card title font"))?;
+    let body_font = Font::try_from_bytes(CARD_BODY_FONT)
+        .ok_or_else(|| anyhow!("unable to load card body font"))?;
+    let mut manifest = ImageManifest::load(recipe_dir)?;
+
+    for recipe in load_recipes(recipe_dir)? {
+        let image_path = recipe_dir.join(format!("{}.jpg", recipe.id));
+        let photo_bytes: Option<Vec<u8>> = match image_path.exists() {
+            true => Some(std::fs::read(&image_path)?),
+            false => None,
+        };
+        let mut photo: Option<DynamicImage> = None;
+
+        for locale in &recipe.locales {
+            if !site_locales.contains(locale) {
+                continue;
+            }
+
+            let card_file = recipe.share_card_file(locale);
+            let card_path = recipe_dir.join(&card_file);
+            let partial = recipe.to_partial(
+                Some(locale.to_string()),
+                site_locales,
+                Vec::new(),
+                None,
+                taxonomy,
+            )?;
+            let card_hash = share_card_hash(&partial, &recipe.category, photo_bytes.as_deref());
+            if card_path.exists() && manifest.share_cards.get(&card_file) == Some(&card_hash) {
+                println!("SKIP {}", card_path.display());
+                continue;
+            }
+            println!("generating share card {}", card_path.display());
+
+            if photo.is_none() {
+                if let Some(photo_bytes) = &photo_bytes {
+                    photo = Some(
+                        image::load_from_memory(photo_bytes)
+                            .map_err(|err| anyhow!("{}: {}", image_path.display(), err))?,
+                    );
+                }
+            }
+            let card = share_card(
+                &partial,
+                &recipe.category,
+                photo.as_ref(),
+                &title_font,
+                &body_font,
+            );
+
+            let mut encoded: Vec<u8> = Vec::new();
+            JpegEncoder::new_with_quality(&mut encoded, CARD_QUALITY).encode_image(&card)?;
+            std::fs::write(&card_path, encoded)?;
+            manifest.share_cards.insert(card_file, card_hash);
+        }
     }
 
-    Ok(())
+    manifest.save(recipe_dir)
+}
+
+/// A hash of everything a share card shows: the localized name, cuisine and
+/// total time of the recipe, the category its color comes from and the
+/// photo.
+fn share_card_hash(partial: &RecipePartial, category: &str, photo: Option<&[u8]>) -> String {
+    let mut fnv_hasher = FnvHasher::default();
+    for text in [
+        CARD_LAYOUT,
+        &partial.name,
+        &partial.cuisine,
+        partial.total_time.as_deref().unwrap_or_default(),
+        category,
+    ] {
+        fnv_hasher.write(text.as_bytes());
+        fnv_hasher.write_u8(0);
+    }
+    fnv_hasher.write(photo.unwrap_or_default());
+    format!("{:016x}", fnv_hasher.finish())
+}
+
+fn share_card(
+    partial: &RecipePartial,
+    category: &str,
+    photo: Option<&DynamicImage>,
+    title_font: &Font,
+    body_font: &Font,
+) -> RgbImage {
+    let mut card = match photo {
+        Some(photo) => {
+            let mut background = photo
+                .resize_to_fill(CARD_WIDTH, CARD_HEIGHT, FilterType::Triangle)
+                .to_rgb8();
+            darken(&mut background);
+            background
+        }
+        None => RgbImage::from_pixel(CARD_WIDTH, CARD_HEIGHT, Rgb(category_color(category))),
+    };
+
+    let white = Rgb([0xff, 0xff, 0xff]);
+    let title_scale = Scale::uniform(72.0);
+    let body_scale = Scale::uniform(40.0);
+    let max_width = (CARD_WIDTH as i32 - CARD_MARGIN * 2) as f32;
+
+    let mut title_lines = wrap_text(title_font, title_scale, &partial.name, max_width);
+    title_lines.truncate(4);
+
+    let mut details = vec![partial.cuisine.clone()];
+    if let Some(total_time) = &partial.total_time {
+        details.push(total_time.clone());
+    }
+
+    let line_height = 86;
+    let title_height = line_height * title_lines.len() as i32;
+    let mut y = (CARD_HEIGHT as i32 - title_height - 64) / 2;
+    for line in &title_lines {
+        draw_text(
+            &mut card,
+            title_font,
+            title_scale,
+            CARD_MARGIN,
+            y,
+            line,
+            white,
+        );
+        y += line_height;
+    }
+
+    draw_text(
+        &mut card,
+        body_font,
+        body_scale,
+        CARD_MARGIN,
+        y + 16,
+        &details.join(" \u{b7} "),
+        white,
+    );
+
+    card
+}
+
+/// Darken a photo so that white text drawn over it stays readable.
+fn darken(image: &mut RgbImage) {
+    for pixel in image.pixels_mut() {
+        for channel in pixel.0.iter_mut() {
+            *channel = (*channel as u16 * 2 / 5) as u8;
+        }
+    }
+}
+
+fn category_color(category: &str) -> [u8; 3] {
+    let mut fnv_hasher = FnvHasher::default();
+    fnv_hasher.write(category.to_lowercase().as_bytes());
+    CARD_COLORS[(fnv_hasher.finish() % CARD_COLORS.len() as u64) as usize]
+}
+
+fn text_width(font: &Font, scale: Scale, text: &str) -> f32 {
+    font.layout(text, scale, point(0.0, 0.0))
+        .last()
+        .map(|glyph| glyph.position().x + glyph.unpositioned().h_metrics().advance_width)
+        .unwrap_or_default()
+}
+
+/// Break text into lines that fit within the given width.
+fn wrap_text(font: &Font, scale: Scale, text: &str, max_width: f32) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let candidate = match current.is_empty() {
+            true => word.to_string(),
+            false => format!("{} {}", current, word),
+        };
+        if !current.is_empty() && text_width(font, scale, &candidate) > max_width {
+            lines.push(current);
+            current = word.to_string();
+        } else {
+            current = candidate;
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+fn draw_text(
+    image: &mut RgbImage,
+    font: &Font,
+    scale: Scale,
+    x: i32,
+    y: i32,
+    text: &str,
+    color: Rgb<u8>,
+) {
+    let ascent = font.v_metrics(scale).ascent;
+    for glyph in font.layout(text, scale, point(x as f32, y as f32 + ascent)) {
+        if let Some(bounding_box) = glyph.pixel_bounding_box() {
+            glyph.draw(|gx, gy, coverage| {
+                let px = gx as i32 + bounding_box.min.x;
+                let py = gy as i32 + bounding_box.min.y;
+                if px < 0 || py < 0 || px >= image.width() as i32 || py >= image.height() as i32 {
+                    return;
+                }
+                let pixel = image.get_pixel_mut(px as u32, py as u32);
+                for (channel, target) in pixel.0.iter_mut().zip(color.0.iter()) {
+                    *channel =
+                        (*channel as f32 * (1.0 - coverage) + *target as f32 * coverage) as u8;
+                }
+            });
+        }
+    }
 }
@@ -0,0 +1,418 @@
+use crate::locale::Locale;
+
+/// Units recognized after a quantity, keyed by their canonical name.
+const UNITS: &[(&str, &[&str])] = &[
+    (
+        "teaspoon",
+        &["teaspoon", "teaspoons", "teaspon", "tsp", "tsps"],
+    ),
+    (
+        "tablespoon",
+        &["tablespoon", "tablespoons", "tbsp", "tbsps", "tbs"],
+    ),
+    ("cup", &["cup", "cups"]),
+    ("pint", &["pint", "pints", "pt"]),
+    ("quart", &["quart", "quarts", "qt"]),
+    ("gallon", &["gallon", "gallons", "gal"]),
+    ("fluid ounce", &["fl oz", "floz"]),
+    ("ounce", &["ounce", "ounces", "oz"]),
+    ("pound", &["pound", "pounds", "lb", "lbs"]),
+    ("gram", &["gram", "grams", "g"]),
+    ("kilogram", &["kilogram", "kilograms", "kg"]),
+    ("milliliter", &["milliliter", "milliliters", "ml"]),
+    ("liter", &["liter", "liters", "l"]),
+    ("pinch", &["pinch", "pinches"]),
+    ("dash", &["dash", "dashes"]),
+    ("clove", &["clove", "cloves"]),
+    ("stalk", &["stalk", "stalks"]),
+    ("stick", &["stick", "sticks"]),
+    ("slice", &["slice", "slices"]),
+    ("can", &["can", "cans"]),
+    ("package", &["package", "packages", "pkg"]),
+];
+
+/// Words describing how an ingredient is prepared or sized rather than what
+/// it is. They are left out of canonical names so that "2 small tomatoes" and
+/// "1 cup diced tomatoes" are the same ingredient.
+const DESCRIPTORS: &[&str] = &[
+    "small",
+    "medium",
+    "large",
+    "fresh",
+    "freshly",
+    "ground",
+    "granulated",
+    "finely",
+    "coarsely",
+    "roughly",
+    "chopped",
+    "minced",
+    "diced",
+    "sliced",
+    "crushed",
+    "grated",
+    "melted",
+    "softened",
+    "boneless",
+    "skinless",
+    "whole",
+    "trimmed",
+    "peeled",
+];
+
+const FRACTIONS: &[(char, f64)] = &[
+    ('¼', 0.25),
+    ('½', 0.5),
+    ('¾', 0.75),
+    ('⅓', 1.0 / 3.0),
+    ('⅔', 2.0 / 3.0),
+    ('⅛', 0.125),
+];
+
+/// The amount written at the start of an ingredient, such as `1 1/2` or the
+/// range `3-6`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub amount: f64,
+    pub max: Option<f64>,
+}
+
+/// An ingredient line split into its quantity, unit, name and the notes that
+/// follow a comma or parenthesis. Units are recognized in English only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ingredient {
+    pub quantity: Option<Quantity>,
+    pub unit: Option<&'static str>,
+    pub name: String,
+    pub note: Option<String>,
+}
+
+impl Ingredient {
+    pub fn parse(text: &str) -> Self {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let (quantity, mut index) = match leading_quantity(&words) {
+            Some((quantity, length)) => (Some(quantity), length),
+            None => (None, 0),
+        };
+
+        let mut unit: Option<&'static str> = None;
+        if quantity.is_some() {
+            if let Some((found, length)) = parse_unit(&words[index..]) {
+                unit = Some(found);
+                index += length;
+                if words.get(index).map(|word| word.to_lowercase()) == Some(String::from("of")) {
+                    index += 1;
+                }
+            }
+        }
+
+        let rest = words[index..].join(" ");
+        let (name, note) = split_note(&rest);
+
+        Ingredient {
+            quantity,
+            unit,
+            name,
+            note,
+        }
+    }
+
+    /// The lowercase name of the ingredient without leading measures or
+    /// descriptors, used to match the same ingredient across recipes.
+    pub fn canonical_name(&self) -> String {
+        canonical(&self.name)
+    }
+}
+
+/// Rewrite the quantity an ingredient line starts with in the number format
+/// of a locale, such as `1½ cups flour` for `1 1/2 cups flour`. Lines without
+/// a quantity are returned unchanged.
+pub fn localize_quantity(text: &str, locale: &Locale) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let (quantity, length) = match leading_quantity(&words) {
+        Some(found) => found,
+        None => return text.to_string(),
+    };
+
+    let mut rest = text.trim_start();
+    for _ in 0..length {
+        rest = rest.trim_start();
+        rest = &rest[rest.find(char::is_whitespace).unwrap_or(rest.len())..];
+    }
+
+    let formatted = match quantity.max {
+        Some(max) => format!(
+            "{}-{}",
+            locale.format_quantity(quantity.amount),
+            locale.format_quantity(max)
+        ),
+        None => locale.format_quantity(quantity.amount),
+    };
+    format!("{}{}", formatted, rest)
+}
+
+/// The lowercase name of a piece of equipment without a leading size or the
+/// alternatives that follow "or", e.g. "dutch oven" for "5 quart dutch oven or
+/// similar stove top pot".
+pub fn canonical_equipment(text: &str) -> String {
+    let name = Ingredient::parse(text).canonical_name();
+    match name.split_once(" or ") {
+        Some((first, _)) => first.to_string(),
+        None => name,
+    }
+}
+
+/// The candidate, such as a food or a store section, whose words are found
+/// in an ingredient name. The longest match wins, then the one nearest the
+/// end, so "chicken broth" is broth rather than chicken. `same_word`
+/// compares a word of the name with a word of a candidate.
+pub fn best_match<'a, T>(
+    name: &str,
+    candidates: impl IntoIterator<Item = (T, &'a str)>,
+    same_word: impl Fn(&str, &str) -> bool,
+) -> Option<T> {
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let mut found: Option<(T, (usize, usize))> = None;
+    for (candidate, candidate_name) in candidates {
+        let candidate_words: Vec<&str> = candidate_name.split(' ').collect();
+        let end = words
+            .windows(candidate_words.len())
+            .rposition(|window| {
+                window
+                    .iter()
+                    .zip(&candidate_words)
+                    .all(|(word, candidate_word)| same_word(word, candidate_word))
+            })
+            .map(|start| start + candidate_words.len());
+        if let Some(end) = end {
+            let rank = (candidate_words.len(), end);
+            if found.as_ref().is_none_or(|(_, best)| rank > *best) {
+                found = Some((candidate, rank));
+            }
+        }
+    }
+    found.map(|(candidate, _)| candidate)
+}
+
+fn canonical(name: &str) -> String {
+    let words: Vec<String> = name
+        .split_whitespace()
+        .map(|word| word.trim_matches([',', ';', '.']).to_lowercase())
+        .filter(|word| !word.is_empty())
+        .skip_while(|word| {
+            word.chars().any(|c| c.is_ascii_digit())
+                || word == "/"
+                || word == "of"
+                || unit(word).is_some()
+                || DESCRIPTORS.contains(&word.as_str())
+        })
+        .filter(|word| !DESCRIPTORS.contains(&word.as_str()))
+        .collect();
+    words.join(" ")
+}
+
+/// Split an ingredient name from the notes that follow it. A leading segment
+/// made only of descriptors, as in "boneless, skinless chicken", is kept with
+/// the name.
+fn split_note(text: &str) -> (String, Option<String>) {
+    let mut end = 0;
+    loop {
+        let next = match text[end..].find([',', '(']) {
+            Some(offset) => end + offset,
+            None => return (text.trim().to_string(), None),
+        };
+        if !canonical(&text[..next]).is_empty() {
+            let note = text[next..]
+                .trim_start_matches([',', ' '])
+                .trim()
+                .to_string();
+            return (
+                text[..next].trim().to_string(),
+                match note.is_empty() {
+                    true => None,
+                    false => Some(note),
+                },
+            );
+        }
+        end = next + 1;
+    }
+}
+
+fn unit(word: &str) -> Option<&'static str> {
+    let word = word.trim_end_matches('.');
+    UNITS
+        .iter()
+        .find(|(_, aliases)| aliases.contains(&word))
+        .map(|(name, _)| *name)
+}
+
+/// The unit at the start of the words and the number of words it spans.
+fn parse_unit(words: &[&str]) -> Option<(&'static str, usize)> {
+    if words.len() >= 2 {
+        if let Some(found) = unit(&format!("{} {}", words[0], words[1]).to_lowercase()) {
+            return Some((found, 2));
+        }
+    }
+    unit(&words.first()?.to_lowercase()).map(|found| (found, 1))
+}
+
+/// The quantity at the start of the words, such as `1 1/2`, and the number of
+/// words it spans.
+fn leading_quantity(words: &[&str]) -> Option<(Quantity, usize)> {
+    let mut quantity = parse_quantity(words.first()?)?;
+    if quantity.max.is_none() {
+        if let Some(fraction) = words.get(1).and_then(|word| parse_fraction(word)) {
+            quantity.amount += fraction;
+            return Some((quantity, 2));
+        }
+    }
+    Some((quantity, 1))
+}
+
+fn parse_quantity(word: &str) -> Option<Quantity> {
+    match word.split_once('-') {
+        Some((min, max)) => Some(Quantity {
+            amount: parse_amount(min)?,
+            max: Some(parse_amount(max)?),
+        }),
+        None => Some(Quantity {
+            amount: parse_amount(word)?,
+            max: None,
+        }),
+    }
+}
+
+fn parse_amount(word: &str) -> Option<f64> {
+    if let Some(fraction) = parse_fraction(word) {
+        return Some(fraction);
+    }
+    match word.chars().last() {
+        Some(last) if FRACTIONS.iter().any(|(c, _)| *c == last) => {
+            let whole = &word[..word.len() - last.len_utf8()];
+            Some(whole.parse::<u32>().ok()? as f64 + parse_fraction(&last.to_string())?)
+        }
+        _ => word.parse::<f64>().ok().filter(|amount| amount.is_finite()),
+    }
+}
+
+fn parse_fraction(word: &str) -> Option<f64> {
+    if let Some((numerator, denominator)) = word.split_once('/') {
+        let numerator = numerator.parse::<u32>().ok()?;
+        let denominator = denominator.parse::<u32>().ok()?;
+        return match denominator {
+            0 => None,
+            _ => Some(numerator as f64 / denominator as f64),
+        };
+    }
+    let mut chars = word.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => FRACTIONS
+            .iter()
+            .find(|(fraction, _)| *fraction == c)
+            .map(|(_, value)| *value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ok() {
+        assert_eq!(
+            Ingredient::parse("1 1/2 pounds of boneless, skinless chicken brests, trimmed"),
+            Ingredient {
+                quantity: Some(Quantity {
+                    amount: 1.5,
+                    max: None
+                }),
+                unit: Some("pound"),
+                name: String::from("boneless, skinless chicken brests"),
+                note: Some(String::from("trimmed")),
+            }
+        );
+        assert_eq!(
+            Ingredient::parse("3-6 lime wedges"),
+            Ingredient {
+                quantity: Some(Quantity {
+                    amount: 3.0,
+                    max: Some(6.0)
+                }),
+                unit: None,
+                name: String::from("lime wedges"),
+                note: None,
+            }
+        );
+        assert_eq!(
+            Ingredient::parse("1½ cup crushed pinapple (I just buy a small can)"),
+            Ingredient {
+                quantity: Some(Quantity {
+                    amount: 1.5,
+                    max: None
+                }),
+                unit: Some("cup"),
+                name: String::from("crushed pinapple"),
+                note: Some(String::from("(I just buy a small can)")),
+            }
+        );
+        assert_eq!(Ingredient::parse("salt to taste").quantity, None);
+    }
+
+    #[test]
+    fn canonical_name_ok() {
+        for (text, expected) in [
+            ("3 tablespoons of unsalted butter", "unsalted butter"),
+            (
+                "1 1/4 cups unsalted butter, 10 oz or 2 1/2 sticks, melted",
+                "unsalted butter",
+            ),
+            ("2 small tomatoes", "tomatoes"),
+            ("1 1-pound 12-ounce can of diced tomatoes", "tomatoes"),
+            ("1 tsp ground cinnamon", "cinnamon"),
+            ("2 Tbsp lemon juice, juice of 1/2 lemon", "lemon juice"),
+            (
+                "4 1/2 cup / 540 g high gluten/bread flour",
+                "high gluten/bread flour",
+            ),
+            ("5 stalks of celery", "celery"),
+            (
+                "1 1/2 pounds of boneless, skinless chicken brests, trimmed",
+                "chicken brests",
+            ),
+        ] {
+            assert_eq!(Ingredient::parse(text).canonical_name(), expected);
+        }
+        assert_eq!(
+            canonical_equipment("5 quart dutch oven or similar stove top pot"),
+            "dutch oven"
+        );
+        assert_eq!(canonical_equipment("Dutch oven"), "dutch oven");
+    }
+
+    #[test]
+    fn localize_quantity_ok() {
+        let en_us = crate::locale::lookup("en_US").unwrap();
+        let fr_fr = crate::locale::lookup("fr_FR").unwrap();
+        assert_eq!(
+            localize_quantity("1 1/2 cups flour", en_us),
+            "1½ cups flour"
+        );
+        assert_eq!(
+            localize_quantity("3/4 pound smoked sausage", en_us),
+            "¾ pound smoked sausage"
+        );
+        assert_eq!(
+            localize_quantity("1 1/2 tasse de farine", fr_fr),
+            "1,5 tasse de farine"
+        );
+        assert_eq!(
+            localize_quantity("3-6 cloves garlic", fr_fr),
+            "3-6 cloves garlic"
+        );
+        assert_eq!(localize_quantity("salt to taste", fr_fr), "salt to taste");
+    }
+}
@@ -6,9 +6,25 @@ extern crate log;
 
 extern crate slugify;
 
+pub mod calendar;
+pub mod catalog;
+pub mod check;
+pub mod config;
+pub mod ingredient;
+pub mod locale;
+pub mod menu;
 pub mod model;
+pub mod nutrition;
+pub mod patch;
+pub mod schedule;
+pub mod shopping;
 pub mod site;
+pub mod step;
+pub mod structured_data;
+pub mod tag;
+pub mod taxonomy;
 pub mod template;
+pub mod translate;
 pub mod when;
 
 #[cfg(feature = "validate")]
@@ -0,0 +1,607 @@
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::time::Duration;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::model::US_ENGLISH;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextDirection {
+    Ltr,
+    Rtl,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    Imperial,
+    Metric,
+}
+
+/// The CLDR plural rules used to pick a plural form for a count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluralRule {
+    /// `one` for exactly 1, otherwise `other`.
+    OneOther,
+    /// `one` for 0 and 1, otherwise `other`.
+    French,
+    /// `zero`, `one`, `two`, `few`, `many` and `other`.
+    Arabic,
+}
+
+/// Message patterns keyed by plural category, with a `{count}` placeholder.
+pub type PluralForms = &'static [(&'static str, &'static str)];
+
+/// The words used to write durations in a locale.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DurationUnits {
+    pub hours: PluralForms,
+    pub minutes: PluralForms,
+}
+
+const ENGLISH_DURATIONS: DurationUnits = DurationUnits {
+    hours: &[("one", "{count} hour"), ("other", "{count} hours")],
+    minutes: &[("one", "{count} minute"), ("other", "{count} minutes")],
+};
+
+const FRENCH_DURATIONS: DurationUnits = DurationUnits {
+    hours: &[("one", "{count} heure"), ("other", "{count} heures")],
+    minutes: &[("one", "{count} minute"), ("other", "{count} minutes")],
+};
+
+const GERMAN_DURATIONS: DurationUnits = DurationUnits {
+    hours: &[("one", "{count} Stunde"), ("other", "{count} Stunden")],
+    minutes: &[("one", "{count} Minute"), ("other", "{count} Minuten")],
+};
+
+const SPANISH_DURATIONS: DurationUnits = DurationUnits {
+    hours: &[("one", "{count} hora"), ("other", "{count} horas")],
+    minutes: &[("one", "{count} minuto"), ("other", "{count} minutos")],
+};
+
+const ITALIAN_DURATIONS: DurationUnits = DurationUnits {
+    hours: &[("one", "{count} ora"), ("other", "{count} ore")],
+    minutes: &[("one", "{count} minuto"), ("other", "{count} minuti")],
+};
+
+const PORTUGUESE_DURATIONS: DurationUnits = DurationUnits {
+    hours: &[("one", "{count} hora"), ("other", "{count} horas")],
+    minutes: &[("one", "{count} minuto"), ("other", "{count} minutos")],
+};
+
+const GREEK_DURATIONS: DurationUnits = DurationUnits {
+    hours: &[("one", "{count} ώρα"), ("other", "{count} ώρες")],
+    minutes: &[("one", "{count} λεπτό"), ("other", "{count} λεπτά")],
+};
+
+const ARABIC_DURATIONS: DurationUnits = DurationUnits {
+    hours: &[
+        ("zero", "{count} ساعة"),
+        ("one", "ساعة واحدة"),
+        ("two", "ساعتان"),
+        ("few", "{count} ساعات"),
+        ("many", "{count} ساعة"),
+        ("other", "{count} ساعة"),
+    ],
+    minutes: &[
+        ("zero", "{count} دقيقة"),
+        ("one", "دقيقة واحدة"),
+        ("two", "دقيقتان"),
+        ("few", "{count} دقائق"),
+        ("many", "{count} دقيقة"),
+        ("other", "{count} دقيقة"),
+    ],
+};
+
+const HEBREW_DURATIONS: DurationUnits = DurationUnits {
+    hours: &[("one", "שעה אחת"), ("other", "{count} שעות")],
+    minutes: &[("one", "דקה אחת"), ("other", "{count} דקות")],
+};
+
+/// Common cooking fractions and the characters used to write them.
+const FRACTIONS: &[(f64, &str)] = &[
+    (0.125, "⅛"),
+    (0.25, "¼"),
+    (1.0 / 3.0, "⅓"),
+    (0.375, "⅜"),
+    (0.5, "½"),
+    (0.625, "⅝"),
+    (2.0 / 3.0, "⅔"),
+    (0.75, "¾"),
+    (0.875, "⅞"),
+];
+
+/// A locale the site knows how to publish.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Locale {
+    pub id: &'static str,
+    /// The name of the locale in its own language.
+    pub name: &'static str,
+    pub direction: TextDirection,
+    /// Locales to try, in order, when a string has no translation for this
+    /// locale. `en_US` is always tried last.
+    pub fallbacks: &'static [&'static str],
+    pub units: Units,
+    /// A date pattern using `YYYY`, `MM` and `DD` placeholders.
+    pub date_format: &'static str,
+    pub plurals: PluralRule,
+    /// Letters that sort as their own letter, after their base letter, rather
+    /// than as an accented variant of it.
+    pub collation: &'static [char],
+    pub durations: DurationUnits,
+    pub decimal_separator: char,
+    /// Whether quantities are written with fractions such as `1½` rather
+    /// than decimals.
+    pub fractions: bool,
+}
+
+pub const LOCALES: &[Locale] = &[
+    Locale {
+        id: "en_US",
+        name: "English (United States)",
+        direction: TextDirection::Ltr,
+        fallbacks: &[],
+        units: Units::Imperial,
+        date_format: "MM/DD/YYYY",
+        plurals: PluralRule::OneOther,
+        collation: &[],
+        durations: ENGLISH_DURATIONS,
+        decimal_separator: '.',
+        fractions: true,
+    },
+    Locale {
+        id: "en_GB",
+        name: "English (United Kingdom)",
+        direction: TextDirection::Ltr,
+        fallbacks: &["en_US"],
+        units: Units::Metric,
+        date_format: "DD/MM/YYYY",
+        plurals: PluralRule::OneOther,
+        collation: &[],
+        durations: ENGLISH_DURATIONS,
+        decimal_separator: '.',
+        fractions: true,
+    },
+    Locale {
+        id: "en_CA",
+        name: "English (Canada)",
+        direction: TextDirection::Ltr,
+        fallbacks: &["en_GB", "en_US"],
+        units: Units::Metric,
+        date_format: "YYYY-MM-DD",
+        plurals: PluralRule::OneOther,
+        collation: &[],
+        durations: ENGLISH_DURATIONS,
+        decimal_separator: '.',
+        fractions: true,
+    },
+    Locale {
+        id: "fr_FR",
+        name: "Français (France)",
+        direction: TextDirection::Ltr,
+        fallbacks: &["en_US"],
+        units: Units::Metric,
+        date_format: "DD/MM/YYYY",
+        plurals: PluralRule::French,
+        collation: &[],
+        durations: FRENCH_DURATIONS,
+        decimal_separator: ',',
+        fractions: false,
+    },
+    Locale {
+        id: "fr_CA",
+        name: "Français (Canada)",
+        direction: TextDirection::Ltr,
+        fallbacks: &["fr_FR", "en_US"],
+        units: Units::Metric,
+        date_format: "YYYY-MM-DD",
+        plurals: PluralRule::French,
+        collation: &[],
+        durations: FRENCH_DURATIONS,
+        decimal_separator: ',',
+        fractions: false,
+    },
+    Locale {
+        id: "de_DE",
+        name: "Deutsch (Deutschland)",
+        direction: TextDirection::Ltr,
+        fallbacks: &["en_US"],
+        units: Units::Metric,
+        date_format: "DD.MM.YYYY",
+        plurals: PluralRule::OneOther,
+        collation: &[],
+        durations: GERMAN_DURATIONS,
+        decimal_separator: ',',
+        fractions: false,
+    },
+    Locale {
+        id: "es_ES",
+        name: "Español (España)",
+        direction: TextDirection::Ltr,
+        fallbacks: &["en_US"],
+        units: Units::Metric,
+        date_format: "DD/MM/YYYY",
+        plurals: PluralRule::OneOther,
+        collation: &['ñ'],
+        durations: SPANISH_DURATIONS,
+        decimal_separator: ',',
+        fractions: false,
+    },
+    Locale {
+        id: "es_MX",
+        name: "Español (México)",
+        direction: TextDirection::Ltr,
+        fallbacks: &["es_ES", "en_US"],
+        units: Units::Metric,
+        date_format: "DD/MM/YYYY",
+        plurals: PluralRule::OneOther,
+        collation: &['ñ'],
+        durations: SPANISH_DURATIONS,
+        decimal_separator: '.',
+        fractions: false,
+    },
+    Locale {
+        id: "it_IT",
+        name: "Italiano (Italia)",
+        direction: TextDirection::Ltr,
+        fallbacks: &["en_US"],
+        units: Units::Metric,
+        date_format: "DD/MM/YYYY",
+        plurals: PluralRule::OneOther,
+        collation: &[],
+        durations: ITALIAN_DURATIONS,
+        decimal_separator: ',',
+        fractions: false,
+    },
+    Locale {
+        id: "pt_BR",
+        name: "Português (Brasil)",
+        direction: TextDirection::Ltr,
+        fallbacks: &["en_US"],
+        units: Units::Metric,
+        date_format: "DD/MM/YYYY",
+        plurals: PluralRule::French,
+        collation: &[],
+        durations: PORTUGUESE_DURATIONS,
+        decimal_separator: ',',
+        fractions: false,
+    },
+    Locale {
+        id: "el_GR",
+        name: "Ελληνικά (Ελλάδα)",
+        direction: TextDirection::Ltr,
+        fallbacks: &["en_US"],
+        units: Units::Metric,
+        date_format: "DD/MM/YYYY",
+        plurals: PluralRule::OneOther,
+        collation: &[],
+        durations: GREEK_DURATIONS,
+        decimal_separator: ',',
+        fractions: false,
+    },
+    Locale {
+        id: "ar_SA",
+        name: "العربية (السعودية)",
+        direction: TextDirection::Rtl,
+        fallbacks: &["en_US"],
+        units: Units::Metric,
+        date_format: "DD/MM/YYYY",
+        plurals: PluralRule::Arabic,
+        collation: &[],
+        durations: ARABIC_DURATIONS,
+        decimal_separator: '.',
+        fractions: false,
+    },
+    Locale {
+        id: "he_IL",
+        name: "עברית (ישראל)",
+        direction: TextDirection::Rtl,
+        fallbacks: &["en_US"],
+        units: Units::Metric,
+        date_format: "DD.MM.YYYY",
+        plurals: PluralRule::OneOther,
+        collation: &[],
+        durations: HEBREW_DURATIONS,
+        decimal_separator: '.',
+        fractions: false,
+    },
+];
+
+/// Find a locale in the registry.
+pub fn lookup(id: &str) -> Option<&'static Locale> {
+    LOCALES.iter().find(|locale| locale.id == id)
+}
+
+/// Find a locale in the registry, using `en_US` for unknown locales.
+pub fn lookup_or_default(id: &str) -> &'static Locale {
+    lookup(id).unwrap_or(&LOCALES[0])
+}
+
+/// The locales to search, in order, for a string in the given locale. The
+/// chain starts with the locale itself and always ends with `en_US`.
+pub fn fallback_chain(id: &str) -> Vec<&str> {
+    let mut chain: Vec<&str> = vec![id];
+
+    if let Some(locale) = lookup(id) {
+        for fallback in locale.fallbacks {
+            if !chain.contains(fallback) {
+                chain.push(fallback);
+            }
+        }
+    }
+
+    if !chain.contains(&US_ENGLISH) {
+        chain.push(US_ENGLISH);
+    }
+
+    chain
+}
+
+/// The BCP 47 language tag of a locale, as used by `hreflang` attributes.
+pub fn hreflang(id: &str) -> String {
+    id.replace('_', "-")
+}
+
+impl Locale {
+    /// The plural category for a count, as used by message catalogs.
+    pub fn plural_category(&self, count: f64) -> &'static str {
+        plural_category(self.plurals, count)
+    }
+
+    /// Compare text in the locale alphabetical order. Case and accents are
+    /// ignored unless the texts are otherwise equal.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.collation_key(a)
+            .cmp(&self.collation_key(b))
+            .then_with(|| a.cmp(b))
+    }
+
+    /// The letter text is listed under in an alphabetical index. Text that
+    /// starts with a number is listed under `#`.
+    pub fn index_letter(&self, text: &str) -> String {
+        let first = match text.chars().find(|c| c.is_alphanumeric()) {
+            Some(first) => first,
+            None => return String::from("#"),
+        };
+        if first.is_numeric() {
+            return String::from("#");
+        }
+        let lowercase = first.to_lowercase().collect::<String>();
+        match self.collation.iter().any(|c| lowercase.starts_with(*c)) {
+            true => lowercase.to_uppercase(),
+            false => strip_accents(&lowercase).to_uppercase(),
+        }
+    }
+
+    fn collation_key(&self, text: &str) -> String {
+        let mut key = String::with_capacity(text.len());
+        for c in text.to_lowercase().chars() {
+            if self.collation.contains(&c) {
+                // Sorts the tailored letter after every other use of its base
+                // letter.
+                key.push_str(&strip_accents(&c.to_string()));
+                key.push(char::MAX);
+            } else {
+                key.push_str(&strip_accents(&c.to_string()));
+            }
+        }
+        key
+    }
+
+    /// Write a duration in hours and minutes, for example `1 hour 10 minutes`.
+    /// Durations are rounded to the minute.
+    pub fn format_duration(&self, duration: Duration) -> String {
+        let total_minutes = ((duration.as_secs() + 30) / 60).max(1);
+        let hours = total_minutes / 60;
+        let minutes = total_minutes % 60;
+
+        let mut parts: Vec<String> = Vec::new();
+        if hours > 0 {
+            parts.push(self.plural_form(self.durations.hours, hours as f64));
+        }
+        if minutes > 0 {
+            parts.push(self.plural_form(self.durations.minutes, minutes as f64));
+        }
+        parts.join(" ")
+    }
+
+    /// Write a number with up to two decimal places and the locale decimal
+    /// separator.
+    pub fn format_number(&self, value: f64) -> String {
+        let formatted = format!("{:.2}", value);
+        let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+        trimmed.replace('.', &self.decimal_separator.to_string())
+    }
+
+    /// Write a quantity, using fractions such as `1½` in locales that use them
+    /// when the quantity is close to a common fraction.
+    pub fn format_quantity(&self, value: f64) -> String {
+        if self.fractions {
+            let whole = value.trunc();
+            if let Some((_, fraction)) = FRACTIONS
+                .iter()
+                .find(|(fraction, _)| (value - whole - fraction).abs() < 0.01)
+            {
+                return match whole == 0.0 {
+                    true => fraction.to_string(),
+                    false => format!("{}{}", whole, fraction),
+                };
+            }
+        }
+        self.format_number(value)
+    }
+
+    fn plural_form(&self, forms: PluralForms, count: f64) -> String {
+        let category = self.plural_category(count);
+        forms
+            .iter()
+            .find(|(form, _)| *form == category)
+            .or_else(|| forms.iter().find(|(form, _)| *form == "other"))
+            .map(|(_, pattern)| pattern.replace("{count}", &self.format_number(count)))
+            .unwrap_or_else(|| self.format_number(count))
+    }
+
+    /// Format an ISO 8601 `YYYY-MM-DD` date with the locale date pattern.
+    /// Values that are not ISO dates are returned unchanged.
+    pub fn format_date(&self, date: &str) -> String {
+        let parts: Vec<&str> = date.split('-').collect();
+        match parts.as_slice() {
+            [year, month, day] if year.len() == 4 && month.len() == 2 && day.len() == 2 => self
+                .date_format
+                .replace("YYYY", year)
+                .replace("MM", month)
+                .replace("DD", day),
+            _ => date.to_string(),
+        }
+    }
+}
+
+fn strip_accents(text: &str) -> String {
+    text.nfd().filter(|c| !is_combining_mark(*c)).collect()
+}
+
+pub fn plural_category(rule: PluralRule, count: f64) -> &'static str {
+    let integer = count.fract() == 0.0;
+    let n = count.abs();
+    match rule {
+        PluralRule::OneOther => match integer && n == 1.0 {
+            true => "one",
+            false => "other",
+        },
+        PluralRule::French => match n < 2.0 {
+            true => "one",
+            false => "other",
+        },
+        PluralRule::Arabic => {
+            let hundreds = n % 100.0;
+            match n {
+                _ if !integer => "other",
+                _ if n == 0.0 => "zero",
+                _ if n == 1.0 => "one",
+                _ if n == 2.0 => "two",
+                _ if (3.0..=10.0).contains(&hundreds) => "few",
+                _ if (11.0..=99.0).contains(&hundreds) => "many",
+                _ => "other",
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback_chain_ok() {
+        assert_eq!(fallback_chain("en_US"), vec!["en_US"]);
+        assert_eq!(fallback_chain("fr_CA"), vec!["fr_CA", "fr_FR", "en_US"]);
+        assert_eq!(fallback_chain("en_CA"), vec!["en_CA", "en_GB", "en_US"]);
+        assert_eq!(fallback_chain("xx_XX"), vec!["xx_XX", "en_US"]);
+    }
+
+    #[test]
+    fn hreflang_ok() {
+        assert_eq!(hreflang("en_US"), "en-US");
+        assert_eq!(hreflang("fr_CA"), "fr-CA");
+    }
+
+    #[test]
+    fn plural_category_ok() {
+        assert_eq!(plural_category(PluralRule::OneOther, 1.0), "one");
+        assert_eq!(plural_category(PluralRule::OneOther, 0.0), "other");
+        assert_eq!(plural_category(PluralRule::OneOther, 1.5), "other");
+        assert_eq!(plural_category(PluralRule::French, 0.0), "one");
+        assert_eq!(plural_category(PluralRule::French, 1.5), "one");
+        assert_eq!(plural_category(PluralRule::French, 2.0), "other");
+        assert_eq!(plural_category(PluralRule::Arabic, 0.0), "zero");
+        assert_eq!(plural_category(PluralRule::Arabic, 2.0), "two");
+        assert_eq!(plural_category(PluralRule::Arabic, 5.0), "few");
+        assert_eq!(plural_category(PluralRule::Arabic, 15.0), "many");
+        assert_eq!(plural_category(PluralRule::Arabic, 100.0), "other");
+    }
+
+    #[test]
+    fn compare_ok() {
+        let french = lookup("fr_FR").unwrap();
+        let mut names = vec!["Tarte", "éclair", "Crêpe", "Eclair", "Zucchini"];
+        names.sort_by(|a, b| french.compare(a, b));
+        assert_eq!(
+            names,
+            vec!["Crêpe", "Eclair", "éclair", "Tarte", "Zucchini"]
+        );
+
+        let spanish = lookup("es_ES").unwrap();
+        let mut names = vec!["ñoquis", "Nachos", "Olla", "nuez"];
+        names.sort_by(|a, b| spanish.compare(a, b));
+        assert_eq!(names, vec!["Nachos", "nuez", "ñoquis", "Olla"]);
+    }
+
+    #[test]
+    fn index_letter_ok() {
+        let french = lookup("fr_FR").unwrap();
+        assert_eq!(french.index_letter("éclair"), "E");
+        assert_eq!(french.index_letter("\"Quiche\""), "Q");
+        assert_eq!(french.index_letter("7 layer dip"), "#");
+        assert_eq!(lookup("es_ES").unwrap().index_letter("ñoquis"), "Ñ");
+        assert_eq!(lookup("el_GR").unwrap().index_letter("Ώρα"), "Ω");
+    }
+
+    #[test]
+    fn format_duration_ok() {
+        let english = lookup("en_US").unwrap();
+        assert_eq!(
+            english.format_duration(Duration::from_secs(30 * 60)),
+            "30 minutes"
+        );
+        assert_eq!(
+            english.format_duration(Duration::from_secs(70 * 60)),
+            "1 hour 10 minutes"
+        );
+        assert_eq!(
+            english.format_duration(Duration::from_secs(2 * 3600)),
+            "2 hours"
+        );
+        assert_eq!(
+            lookup("de_DE")
+                .unwrap()
+                .format_duration(Duration::from_secs(30 * 60)),
+            "30 Minuten"
+        );
+        assert_eq!(
+            lookup("ar_SA")
+                .unwrap()
+                .format_duration(Duration::from_secs(2 * 3600)),
+            "ساعتان"
+        );
+    }
+
+    #[test]
+    fn format_quantity_ok() {
+        let english = lookup("en_US").unwrap();
+        let french = lookup("fr_FR").unwrap();
+        assert_eq!(english.format_quantity(1.5), "1½");
+        assert_eq!(english.format_quantity(0.25), "¼");
+        assert_eq!(english.format_quantity(0.333), "⅓");
+        assert_eq!(english.format_quantity(2.0), "2");
+        assert_eq!(english.format_quantity(1.1), "1.1");
+        assert_eq!(french.format_quantity(1.5), "1,5");
+        assert_eq!(french.format_number(0.75), "0,75");
+        assert_eq!(french.format_number(100.0), "100");
+        assert_eq!(french.format_number(250.0), "250");
+    }
+
+    #[test]
+    fn format_date_ok() {
+        assert_eq!(
+            lookup("en_US").unwrap().format_date("2022-01-02"),
+            "01/02/2022"
+        );
+        assert_eq!(
+            lookup("de_DE").unwrap().format_date("2022-01-02"),
+            "02.01.2022"
+        );
+        assert_eq!(lookup("de_DE").unwrap().format_date("soon"), "soon");
+    }
+}
@@ -6,6 +6,7 @@ extern crate anyhow;
 
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use structopt::StructOpt;
 use url::Url;
 
@@ -18,19 +19,32 @@ use tower_http::services::ServeDir;
 
 use uuid::Uuid;
 
-use jrb::model::{Recipe, SiteView};
-use jrb::site::build_site;
+use jrb::calendar::{schedule_ics, Date};
+use jrb::catalog::Catalog;
+use jrb::check::check_structured_data;
+use jrb::config::{Config, CONFIG_FILE};
+use jrb::locale;
+use jrb::model::{Recipe, SiteView, US_ENGLISH};
+use jrb::nutrition::NutrientTable;
+use jrb::schedule::{format_clock, parse_clock, Schedule};
+use jrb::shopping::{find_recipe, ShoppingList, ShoppingListFormat};
+use jrb::site::{build_site, load_recipes};
+use jrb::step::extract_steps;
+use jrb::taxonomy::Taxonomy;
+use jrb::translate::{export_translations, import_translations, translation_status};
 
 #[cfg(feature = "validate")]
-use jrb::validate::validate_recipes;
+use jrb::validate::{validate_menus, validate_nutrition, validate_recipes};
 
 #[cfg(feature = "convert")]
-use jrb::image::generate_thumbnails;
+use jrb::image::{generate_share_cards, generate_thumbnails};
 
 pub mod built_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 
+const DEFAULT_PUBLIC_URL: &str = "http://localhost:8080/";
+
 fn parse_url(src: &str) -> Result<String, anyhow::Error> {
     let mut base_url = Url::parse(src)?;
 
@@ -51,39 +65,102 @@ fn parse_url(src: &str) -> Result<String, anyhow::Error> {
 }
 
 #[derive(Debug, StructOpt, Clone)]
-#[structopt(about = "The justrecipes.blog builder", version=built_info::PKG_VERSION)]
+#[structopt(about = "A static recipe site builder", version=built_info::PKG_VERSION)]
 struct Opt {
-    #[structopt(long, parse(from_os_str), default_value = "recipes")]
+    #[structopt(long, parse(from_os_str), default_value = CONFIG_FILE)]
+    /// The project configuration file. Options given on the command line
+    /// take precedence over it.
+    config: PathBuf,
+
+    #[structopt(long, parse(from_os_str))]
     /// The directory that contains recipe yaml files.
-    recipe_dir: PathBuf,
+    recipe_dir: Option<PathBuf>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// The directory that contains menu yaml files.
+    menu_dir: Option<PathBuf>,
 
-    #[structopt(long, parse(from_os_str), default_value = "templates")]
+    #[structopt(long, parse(from_os_str))]
     /// The directory that contains website template files.
-    templates_dir: PathBuf,
+    templates_dir: Option<PathBuf>,
 
-    #[structopt(long, parse(from_os_str), default_value = "static")]
+    #[structopt(long, parse(from_os_str))]
     /// The directory that contains static assets like css, js, icons, etc.
-    static_dir: PathBuf,
+    static_dir: Option<PathBuf>,
 
-    #[structopt(long, parse(from_os_str), default_value = "public")]
+    #[structopt(long, parse(from_os_str))]
     /// The directory that contains static and generated content.
-    public_dir: PathBuf,
+    public_dir: Option<PathBuf>,
 
-    #[structopt(long, default_value = "en_US")]
+    #[structopt(long, parse(from_os_str))]
+    /// The file that lists the categories and cuisines recipes are filed under.
+    taxonomy_file: Option<PathBuf>,
+
+    #[structopt(long, parse(from_os_str))]
+    /// The CSV file of the nutrients of foods that recipe nutrition facts are
+    /// computed from.
+    nutrients_file: Option<PathBuf>,
+
+    #[structopt(long)]
+    /// The locales the site is published in.
     locales: Vec<String>,
 
-    #[structopt(long, parse(try_from_str = parse_url), default_value = "http://localhost:8080/")]
+    #[structopt(long, parse(try_from_str = parse_url))]
     /// The base URL for the generated site.
-    public_url: String,
+    public_url: Option<String>,
 
     #[structopt(subcommand)]
     cmd: Command,
 }
 
+impl Opt {
+    /// Load the configuration file and apply the command line options to it.
+    fn config(&self) -> Result<Config, anyhow::Error> {
+        let mut config = Config::load(&self.config)?;
+
+        let dirs = &mut config.dirs;
+        for (option, dir) in [
+            (&self.recipe_dir, &mut dirs.recipes),
+            (&self.menu_dir, &mut dirs.menus),
+            (&self.templates_dir, &mut dirs.templates),
+            (&self.static_dir, &mut dirs.static_files),
+            (&self.public_dir, &mut dirs.public),
+            (&self.taxonomy_file, &mut dirs.taxonomy_file),
+            (&self.nutrients_file, &mut dirs.nutrients_file),
+        ] {
+            if let Some(option) = option {
+                *dir = option.clone();
+            }
+        }
+
+        if !self.locales.is_empty() {
+            config.locales.enabled = self.locales.clone();
+            if !self.locales.contains(&config.locales.default) {
+                config.locales.default = self.locales[0].clone();
+            }
+        }
+
+        config.site.public_url = Some(match (&self.public_url, &config.site.public_url) {
+            (Some(public_url), _) => public_url.clone(),
+            (None, Some(public_url)) => parse_url(public_url)?,
+            (None, None) => String::from(DEFAULT_PUBLIC_URL),
+        });
+
+        Ok(config)
+    }
+}
+
 #[derive(StructOpt, Debug, Clone)]
 enum Command {
     /// Build the website.
-    Build {},
+    Build {
+        #[structopt(long)]
+        /// Check the structured data of the generated pages.
+        check: bool,
+    },
+
+    /// Check the schema.org Recipe structured data of the generated pages.
+    CheckStructuredData {},
 
     #[cfg(feature = "server")]
     /// Serve the generated website.
@@ -97,9 +174,61 @@ enum Command {
     Validate {},
 
     #[cfg(feature = "convert")]
-    /// Create thumbnails for recipe images.
+    /// Create thumbnails, placeholders and share cards for recipe images.
     Convert {},
 
+    /// Plan when to start each stage of a recipe.
+    Schedule {
+        #[structopt(long, parse(from_os_str))]
+        /// The recipe file to plan.
+        recipe: PathBuf,
+
+        #[structopt(long)]
+        /// The time to serve at, such as 18:30.
+        serve_at: Option<String>,
+
+        #[structopt(long, parse(from_os_str))]
+        /// Write the schedule as an iCalendar file with an event for each
+        /// stage, serving at the configured time unless --serve-at is given.
+        ics: Option<PathBuf>,
+
+        #[structopt(long)]
+        /// The day to serve on in the iCalendar file, such as 2021-11-25.
+        /// Defaults to today.
+        date: Option<Date>,
+    },
+
+    /// Merge the ingredients of recipes into a shopping list grouped by store
+    /// section.
+    ShoppingList {
+        #[structopt(required = true)]
+        /// The ids or slugs of the recipes to shop for.
+        recipes: Vec<String>,
+
+        #[structopt(long)]
+        /// Scale each recipe to this many servings.
+        servings: Option<u32>,
+
+        #[structopt(long, default_value = "markdown")]
+        /// The output format: markdown, text or json.
+        format: ShoppingListFormat,
+
+        #[structopt(long)]
+        /// The locale of the ingredients, defaults to the default locale.
+        locale: Option<String>,
+    },
+
+    /// Propose durations, temperatures and equipment for recipe steps from
+    /// their text.
+    ExtractSteps {
+        #[structopt(long)]
+        /// Write the proposed values to the recipe files.
+        write: bool,
+    },
+
+    /// Exchange recipe translations with translators.
+    I18n(I18nCommand),
+
     /// Generate and stub a new recipe file.
     Init {
         #[structopt(long)]
@@ -113,60 +242,362 @@ enum Command {
     },
 }
 
+#[derive(StructOpt, Debug, Clone)]
+enum I18nCommand {
+    /// Export the translatable strings of every recipe to a PO file.
+    Export {
+        #[structopt(long)]
+        locale: String,
+
+        #[structopt(long, parse(from_os_str))]
+        /// The PO file to write, defaults to <locale>.po.
+        output: Option<PathBuf>,
+    },
+
+    /// Merge translated PO files into the recipe files.
+    Import {
+        #[structopt(parse(from_os_str), required = true)]
+        files: Vec<PathBuf>,
+    },
+
+    /// Report how much of every recipe is translated into the site locales.
+    Status {
+        #[structopt(long)]
+        /// Print the report as JSON.
+        json: bool,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
     let opt = Opt::from_args();
     debug!("{:?}", opt);
 
+    let config = opt.config()?;
+    debug!("{:?}", config);
+    let dirs = &config.dirs;
+
     match opt.cmd {
-        Command::Build {} => cmd_build(
-            &opt.recipe_dir,
-            &opt.static_dir,
-            &opt.templates_dir,
-            &opt.public_dir,
-            &opt.locales,
-            &opt.public_url,
-        ),
+        Command::Build { check } => {
+            cmd_build(&config)?;
+            match check {
+                true => cmd_check_structured_data(&dirs.public),
+                false => Ok(()),
+            }
+        }
+
+        Command::CheckStructuredData {} => cmd_check_structured_data(&dirs.public),
 
         #[cfg(feature = "server")]
-        Command::Server { listen } => cmd_server(&opt.public_dir, &listen).await,
+        Command::Server { listen } => cmd_server(&dirs.public, &listen).await,
 
         #[cfg(feature = "validate")]
-        Command::Validate {} => cmd_validate(&opt.recipe_dir).await,
+        Command::Validate {} => {
+            cmd_validate(
+                &dirs.recipes,
+                &dirs.menus,
+                &dirs.taxonomy_file,
+                match config.features.nutrition {
+                    true => Some(&dirs.nutrients_file),
+                    false => None,
+                },
+            )
+            .await
+        }
 
         #[cfg(feature = "convert")]
-        Command::Convert {} => cmd_convert(&opt.recipe_dir).await,
+        Command::Convert {} => {
+            cmd_convert(&dirs.recipes, &dirs.taxonomy_file, &config.locales.enabled).await
+        }
+
+        Command::Schedule {
+            recipe,
+            serve_at,
+            ics,
+            date,
+        } => cmd_schedule(
+            &recipe,
+            serve_at.as_deref(),
+            ics.as_deref(),
+            date,
+            &config.calendar.serve_at,
+        ),
+
+        Command::ShoppingList {
+            recipes,
+            servings,
+            format,
+            locale,
+        } => cmd_shopping_list(
+            &dirs.recipes,
+            &dirs.templates,
+            &recipes,
+            servings,
+            format,
+            locale.as_deref().unwrap_or(&config.locales.default),
+        ),
 
-        Command::Init { id, name, mock } => cmd_init(&opt.recipe_dir, id, name, mock),
+        Command::ExtractSteps { write } => extract_steps(&dirs.recipes, write),
+
+        Command::I18n(I18nCommand::Export { locale, output }) => {
+            let output = output.unwrap_or_else(|| PathBuf::from(format!("{}.po", locale)));
+            export_translations(&dirs.recipes, &locale, &output)
+        }
+
+        Command::I18n(I18nCommand::Import { files }) => import_translations(&dirs.recipes, &files),
+
+        Command::I18n(I18nCommand::Status { json }) => {
+            cmd_i18n_status(&dirs.recipes, &config.locales.enabled, json)
+        }
+
+        Command::Init { id, name, mock } => cmd_init(&dirs.recipes, id, name, mock),
+    }
+}
+
+fn cmd_build(config: &Config) -> Result<(), anyhow::Error> {
+    let dirs = &config.dirs;
+    let site_locales = &config.locales.enabled;
+    let taxonomy = Taxonomy::load(&dirs.taxonomy_file)?;
+    taxonomy.validate()?;
+
+    for site_locale in site_locales {
+        if locale::lookup(site_locale).is_none() {
+            warn!(
+                "{} is not in the locale registry, falling back to en_US",
+                site_locale
+            );
+        }
+    }
+
+    let nutrient_table = match config.features.nutrition {
+        true => Some(NutrientTable::load(&dirs.nutrients_file)?),
+        false => None,
+    };
+
+    build_site(
+        &dirs.recipes,
+        &dirs.menus,
+        &dirs.static_files,
+        &dirs.templates,
+        &dirs.public,
+        site_locales,
+        &taxonomy,
+        nutrient_table.as_ref(),
+        SiteView::new(
+            config
+                .site
+                .public_url
+                .as_deref()
+                .unwrap_or(DEFAULT_PUBLIC_URL),
+            built_info::PKG_VERSION,
+            config,
+        ),
+    )
+}
+
+fn cmd_check_structured_data(public_dir: &Path) -> Result<(), anyhow::Error> {
+    let reports = check_structured_data(public_dir)?;
+
+    let mut failed = 0;
+    for report in &reports {
+        for error in &report.errors {
+            println!("ERROR: {}: {}", report.page, error);
+        }
+        for warning in &report.warnings {
+            println!("WARNING: {}: {}", report.page, warning);
+        }
+        match report.errors.is_empty() {
+            true => println!("OK: {}", report.page),
+            false => failed += 1,
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} of {} recipe pages have invalid structured data",
+            failed,
+            reports.len()
+        ));
+    }
+    Ok(())
+}
+
+fn cmd_schedule(
+    recipe_file: &Path,
+    serve_at: Option<&str>,
+    ics: Option<&Path>,
+    date: Option<Date>,
+    default_serve_at: &str,
+) -> Result<(), anyhow::Error> {
+    let recipe: Recipe = serde_yaml::from_str(&fs::read_to_string(recipe_file)?)?;
+    let schedule = Schedule::new(&recipe)?;
+    let format_locale = locale::lookup_or_default(US_ENGLISH);
+
+    let serve_at = match serve_at {
+        Some(serve_at) => Some(parse_clock(serve_at)?),
+        None => None,
+    };
+    let starts: Vec<String> = match serve_at {
+        Some(serve_at) => schedule
+            .start_minutes(serve_at)
+            .into_iter()
+            .map(format_clock)
+            .collect(),
+        None => schedule
+            .stages
+            .iter()
+            .map(|stage| format!("+{}", format_clock(stage.start.as_secs() as i64 / 60)))
+            .collect(),
+    };
+
+    println!("{}", recipe.name.localized(None)?);
+    for (stage, start) in schedule.stages.iter().zip(starts) {
+        let duration = stage.end - stage.start;
+        println!(
+            "{} {} {}{}{}",
+            start,
+            match stage.critical {
+                true => "*",
+                false => " ",
+            },
+            recipe.stages[stage.index].name.localized(None)?,
+            match duration.is_zero() {
+                true => String::new(),
+                false => format!(" ({})", format_locale.format_duration(duration)),
+            },
+            match stage.hands_off {
+                true => ", hands-off",
+                false => "",
+            }
+        );
     }
+    match serve_at {
+        Some(serve_at) => println!(
+            "Serve at {}, {} in total",
+            format_clock(serve_at),
+            format_locale.format_duration(schedule.total)
+        ),
+        None => println!("{} in total", format_locale.format_duration(schedule.total)),
+    }
+    println!("* on the critical path");
+
+    if let Some(ics) = ics {
+        let stages = recipe
+            .stages
+            .iter()
+            .enumerate()
+            .map(|(index, stage)| stage.to_partial(index, None))
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+        let serve_at = match serve_at {
+            Some(serve_at) => serve_at,
+            None => parse_clock(default_serve_at)?,
+        };
+        let date = date.unwrap_or_else(Date::today);
+        let calendar = schedule_ics(
+            &recipe,
+            &recipe.name.localized(None)?,
+            &stages,
+            date,
+            serve_at,
+            None,
+            SystemTime::now(),
+        )?;
+        fs::write(ics, calendar)?;
+        println!(
+            "Wrote {} to serve on {} at {}",
+            ics.display(),
+            date,
+            format_clock(serve_at)
+        );
+    }
+    Ok(())
 }
 
-fn cmd_build(
+fn cmd_shopping_list(
     recipe_dir: &Path,
-    static_dir: &Path,
     templates_dir: &Path,
-    public_dir: &Path,
+    keys: &[String],
+    servings: Option<u32>,
+    format: ShoppingListFormat,
+    locale: &str,
+) -> Result<(), anyhow::Error> {
+    let recipes = load_recipes(recipe_dir)?;
+    let selected = keys
+        .iter()
+        .map(|key| Ok((find_recipe(&recipes, key)?, servings)))
+        .collect::<Result<Vec<(&Recipe, Option<u32>)>, anyhow::Error>>()?;
+    let list = ShoppingList::new(&selected, &recipes, locale)?;
+    let catalog = Catalog::load(templates_dir)?;
+    print!("{}", list.render(format, &catalog, locale)?);
+    Ok(())
+}
+
+fn cmd_i18n_status(
+    recipe_dir: &Path,
     site_locales: &[String],
-    public_url: &str,
+    json: bool,
 ) -> Result<(), anyhow::Error> {
-    build_site(
-        recipe_dir,
-        static_dir,
-        templates_dir,
-        public_dir,
-        site_locales,
-        SiteView::new(public_url, &built_info::PKG_VERSION.to_string()),
-    )
+    let statuses = translation_status(recipe_dir, site_locales)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(());
+    }
+
+    for status in &statuses {
+        println!(
+            "{}: {}/{} strings translated ({}%)",
+            status.locale, status.translated, status.total, status.percent
+        );
+        for recipe in &status.recipes {
+            println!(
+                "  {}: {}/{} ({}%){}",
+                recipe.file,
+                recipe.translated,
+                recipe.total,
+                recipe.percent,
+                match recipe.listed {
+                    true => ", listed",
+                    false => "",
+                }
+            );
+            if recipe.listed_untranslated() {
+                println!(
+                    "  WARNING: {} lists {} but has no translated strings",
+                    recipe.file, status.locale
+                );
+            }
+            if !recipe.missing.is_empty() && recipe.translated > 0 {
+                println!("    missing: {}", recipe.missing.join(", "));
+            }
+        }
+    }
+    Ok(())
 }
 
 #[cfg(feature = "validate")]
-async fn cmd_validate(recipe_dir: &Path) -> Result<(), anyhow::Error> {
-    Ok(validate_recipes(recipe_dir)?)
+async fn cmd_validate(
+    recipe_dir: &Path,
+    menu_dir: &Path,
+    taxonomy_file: &Path,
+    nutrients_file: Option<&Path>,
+) -> Result<(), anyhow::Error> {
+    validate_recipes(recipe_dir, taxonomy_file)?;
+    validate_menus(menu_dir, recipe_dir, taxonomy_file)?;
+    match nutrients_file {
+        Some(nutrients_file) => validate_nutrition(recipe_dir, nutrients_file),
+        None => Ok(()),
+    }
 }
 
 #[cfg(feature = "convert")]
-async fn cmd_convert(recipe_dir: &Path) -> Result<(), anyhow::Error> {
-    Ok(generate_thumbnails(recipe_dir)?)
+async fn cmd_convert(
+    recipe_dir: &Path,
+    taxonomy_file: &Path,
+    site_locales: &[String],
+) -> Result<(), anyhow::Error> {
+    generate_thumbnails(recipe_dir)?;
+    generate_share_cards(recipe_dir, &Taxonomy::load(taxonomy_file)?, site_locales)
 }
 
 #[cfg(feature = "server")]
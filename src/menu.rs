@@ -0,0 +1,479 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::catalog::Catalog;
+use crate::locale::lookup_or_default;
+use crate::model::{
+    LinkPartial, LocalizedString, MenuCoursePartial, MenuDayPartial, MenuPartial,
+    MenuRecipePartial, Recipe, ShoppingSectionPartial, TimelinePartial,
+};
+use crate::schedule::{format_clock, parse_clock, Schedule};
+use crate::shopping::ShoppingList;
+use crate::taxonomy::{Taxonomy, TermKind};
+
+/// The courses of a meal, in the order they are served.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Course {
+    Appetizer,
+    Soup,
+    Salad,
+    Main,
+    Side,
+    Dessert,
+    Drink,
+}
+
+impl Course {
+    /// The message key of the label of the course.
+    fn message_key(&self) -> &'static str {
+        match self {
+            Course::Appetizer => "menu.course.appetizer",
+            Course::Soup => "menu.course.soup",
+            Course::Salad => "menu.course.salad",
+            Course::Main => "menu.course.main",
+            Course::Side => "menu.course.side",
+            Course::Dessert => "menu.course.dessert",
+            Course::Drink => "menu.course.drink",
+        }
+    }
+}
+
+/// A recipe served as part of a menu.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MenuEntry {
+    /// The id of the recipe.
+    pub recipe: Uuid,
+    /// The servings to make, when not the servings of the recipe.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub servings: Option<u32>,
+    /// The day of a meal plan the recipe is served on, starting at 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub course: Option<Course>,
+}
+
+/// A menu or meal plan of recipes, read from the menus directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Menu {
+    pub id: Uuid,
+    pub locales: Vec<String>,
+    pub name: LocalizedString,
+    pub slug: LocalizedString,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<LocalizedString>,
+    /// A category id from the taxonomy, such as `holiday`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// The time the menu is served at, such as `18:30`, when not the time set
+    /// in the calendar configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serve_at: Option<String>,
+    pub entries: Vec<MenuEntry>,
+}
+
+impl Menu {
+    /// Check that the menu only uses recipes that exist in every locale of the
+    /// menu, and terms that exist in the taxonomy.
+    pub fn validate(&self, recipes: &[Recipe], taxonomy: &Taxonomy) -> Result<(), anyhow::Error> {
+        if self.locales.is_empty() {
+            return Err(anyhow!("menu {} has no locales", self.id));
+        }
+        for locale in &self.locales {
+            self.name.localized(Some(locale.clone()))?;
+            self.slug.localized(Some(locale.clone()))?;
+        }
+        if self.entries.is_empty() {
+            return Err(anyhow!("menu {} has no entries", self.id));
+        }
+        if let Some(category) = &self.category {
+            if taxonomy.term(TermKind::Category, category).is_none() {
+                return Err(anyhow!("unknown category {}", category));
+            }
+        }
+        if let Some(serve_at) = &self.serve_at {
+            parse_clock(serve_at)?;
+        }
+
+        for entry in &self.entries {
+            let recipe = recipes
+                .iter()
+                .find(|recipe| recipe.id == entry.recipe)
+                .ok_or_else(|| anyhow!("unknown recipe {}", entry.recipe))?;
+            for locale in &self.locales {
+                if !recipe.locales.contains(locale) {
+                    return Err(anyhow!(
+                        "recipe {} is not published in {}",
+                        entry.recipe,
+                        locale
+                    ));
+                }
+            }
+            if entry.servings == Some(0) {
+                return Err(anyhow!("recipe {} has no servings", entry.recipe));
+            }
+            if entry.day == Some(0) {
+                return Err(anyhow!(
+                    "recipe {} is on day 0, days start at 1",
+                    entry.recipe
+                ));
+            }
+            Schedule::new(recipe)?;
+        }
+        Ok(())
+    }
+
+    /// The localized page of the menu. The recipes of the menu are linked to
+    /// with their links in the locale, and timed to be ready at the serve time
+    /// of the menu.
+    pub fn to_partial(
+        &self,
+        locale: &str,
+        recipes: &[Recipe],
+        recipe_links: &HashMap<Uuid, LinkPartial>,
+        default_serve_at: &str,
+        catalog: &Catalog,
+    ) -> Result<MenuPartial, anyhow::Error> {
+        let serve_at = self.serve_at.as_deref().unwrap_or(default_serve_at);
+        let serve_minutes = parse_clock(serve_at)?;
+        let format_locale = lookup_or_default(locale);
+        let recipe = |id: &Uuid| {
+            recipes
+                .iter()
+                .find(|recipe| &recipe.id == id)
+                .ok_or_else(|| anyhow!("unknown recipe {}", id))
+        };
+        let link = |id: &Uuid| {
+            recipe_links
+                .get(id)
+                .cloned()
+                .ok_or_else(|| anyhow!("recipe {} is not published in {}", id, locale))
+        };
+
+        let mut day_numbers: Vec<Option<u32>> = self.entries.iter().map(|e| e.day).collect();
+        day_numbers.sort();
+        day_numbers.dedup();
+
+        let mut days: Vec<MenuDayPartial> = Vec::with_capacity(day_numbers.len());
+        for day in day_numbers {
+            let entries: Vec<&MenuEntry> = self
+                .entries
+                .iter()
+                .filter(|entry| entry.day == day)
+                .collect();
+
+            let mut courses: Vec<Option<Course>> = entries.iter().map(|e| e.course).collect();
+            courses.sort();
+            courses.dedup();
+
+            let mut course_partials: Vec<MenuCoursePartial> = Vec::with_capacity(courses.len());
+            for course in courses {
+                let mut course_recipes: Vec<MenuRecipePartial> = Vec::new();
+                for entry in entries.iter().filter(|entry| entry.course == course) {
+                    course_recipes.push(MenuRecipePartial {
+                        link: link(&entry.recipe)?,
+                        servings: entry.servings.or(recipe(&entry.recipe)?.servings),
+                    });
+                }
+                course_partials.push(MenuCoursePartial {
+                    name: course.map(|course| catalog.translate(locale, course.message_key(), &[])),
+                    recipes: course_recipes,
+                });
+            }
+
+            // Every recipe of the day is ready at the serve time.
+            let mut timeline: Vec<(i64, TimelinePartial)> = Vec::new();
+            for entry in &entries {
+                let recipe = recipe(&entry.recipe)?;
+                let recipe_link = link(&entry.recipe)?;
+                let schedule = Schedule::new(recipe)?;
+                for (stage, start) in schedule
+                    .stages
+                    .iter()
+                    .zip(schedule.start_minutes(serve_minutes))
+                {
+                    let duration = stage.end - stage.start;
+                    timeline.push((
+                        start,
+                        TimelinePartial {
+                            start: format_clock(start),
+                            recipe: recipe_link.label.clone(),
+                            stage: recipe.stages[stage.index]
+                                .name
+                                .localized(Some(locale.to_string()))?,
+                            url: format!("{}#{}", recipe_link.url, stage.anchor),
+                            duration: match duration.is_zero() {
+                                true => None,
+                                false => Some(format_locale.format_duration(duration)),
+                            },
+                            hands_off: stage.hands_off,
+                        },
+                    ));
+                }
+            }
+            timeline.sort_by_key(|(start, _)| *start);
+
+            days.push(MenuDayPartial {
+                day,
+                courses: course_partials,
+                timeline: timeline.into_iter().map(|(_, row)| row).collect(),
+            });
+        }
+
+        let shopping_recipes: Vec<(&Recipe, Option<u32>)> = self
+            .entries
+            .iter()
+            .map(|entry| Ok((recipe(&entry.recipe)?, entry.servings)))
+            .collect::<Result<_, anyhow::Error>>()?;
+        let shopping_list = ShoppingList::new(&shopping_recipes, recipes, locale)?;
+
+        Ok(MenuPartial {
+            id: self.id,
+            name: self.name.localized(Some(locale.to_string()))?,
+            slug: self.slug.localized(Some(locale.to_string()))?,
+            description: match &self.description {
+                Some(description) => Some(description.localized(Some(locale.to_string()))?),
+                None => None,
+            },
+            serve_at: format_clock(serve_minutes),
+            days,
+            shopping_list: shopping_list
+                .sections
+                .iter()
+                .map(|section| ShoppingSectionPartial {
+                    name: catalog.translate(locale, &section.message_key(), &[]),
+                    items: section
+                        .items
+                        .iter()
+                        .map(|item| item.label(catalog, locale))
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+
+    /// The equipment of every recipe of the menu, without repeating equipment
+    /// with the same name in the locale.
+    pub fn equipment(
+        &self,
+        locale: &str,
+        recipes: &[Recipe],
+    ) -> Result<Vec<LocalizedString>, anyhow::Error> {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut equipment: Vec<LocalizedString> = Vec::new();
+        for entry in &self.entries {
+            let recipe = match recipes.iter().find(|recipe| recipe.id == entry.recipe) {
+                Some(recipe) => recipe,
+                None => continue,
+            };
+            for item in recipe.equipment.as_deref().unwrap_or_default() {
+                let name = item.localized(Some(locale.to_string()))?.to_lowercase();
+                if seen.insert(name) {
+                    equipment.push(item.clone());
+                }
+            }
+        }
+        Ok(equipment)
+    }
+}
+
+/// Load every menu in the menu directory along with the file it was read
+/// from, sorted by name. A missing menu directory has no menus.
+pub fn load_menu_files(menu_dir: &Path) -> Result<Vec<(PathBuf, Menu)>, anyhow::Error> {
+    if !menu_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut menu_files: Vec<PathBuf> = walkdir::WalkDir::new(menu_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().display().to_string().ends_with(".yml"))
+        .map(|e| e.path().to_path_buf())
+        .collect();
+    menu_files.sort();
+
+    let mut menus: Vec<(PathBuf, Menu)> = Vec::with_capacity(menu_files.len());
+    let mut menu_ids: HashSet<Uuid> = HashSet::new();
+    for menu_file in menu_files {
+        let menu_yaml = fs::read_to_string(&menu_file)?;
+        let menu: Menu = serde_yaml::from_str(&menu_yaml)
+            .map_err(|err| anyhow!("{}: {}", menu_file.display(), err))?;
+        if !menu_ids.insert(menu.id) {
+            return Err(anyhow!(
+                "duplicate menu id {} in {}",
+                menu.id,
+                menu_file.display()
+            ));
+        }
+        menus.push((menu_file, menu));
+    }
+
+    menus.sort_by(|(_, a), (_, b)| {
+        a.name
+            .localized(None)
+            .unwrap_or_default()
+            .cmp(&b.name.localized(None).unwrap_or_default())
+    });
+    Ok(menus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{IngredientLine, Stage};
+    use std::time::Duration;
+
+    fn recipe(name: &str, minutes: u64, ingredients: &[&str]) -> Recipe {
+        let mut recipe = Recipe::init(None, None, false);
+        recipe.name = LocalizedString::new(name);
+        recipe.servings = Some(4);
+        let mut stage = Stage::init(String::from("Cook"));
+        stage.cook_time = Some(Duration::from_secs(minutes * 60));
+        recipe.stages = vec![stage];
+        recipe.ingredients = ingredients
+            .iter()
+            .map(|text| IngredientLine::new(text))
+            .collect();
+        recipe.equipment = Some(vec![LocalizedString::new("Dutch oven")]);
+        recipe
+    }
+
+    fn menu(recipes: &[Recipe]) -> Menu {
+        serde_yaml::from_str(&format!(
+            "---
+id: 7d0ac5a7-2a5b-4e5e-9f3c-5a3b1c2d4e5f
+locales: [en_US]
+name: Weeknight Dinners
+slug: weeknight-dinners
+entries:
+  - recipe: {}
+    day: 2
+    course: main
+  - recipe: {}
+    servings: 8
+    day: 1
+    course: dessert
+  - recipe: {}
+    day: 1
+    course: main
+",
+            recipes[0].id, recipes[1].id, recipes[0].id
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn menu_ok() {
+        let recipes = vec![
+            recipe("Chili", 90, &["1 cup onions"]),
+            recipe("Pie", 45, &["1 cup sugar", "1/2 cup onions"]),
+        ];
+        let menu = menu(&recipes);
+        let taxonomy = Taxonomy::default();
+        assert!(menu.validate(&recipes, &taxonomy).is_ok());
+
+        let links: HashMap<Uuid, LinkPartial> = recipes
+            .iter()
+            .map(|recipe| {
+                let name = recipe.name.localized(None).unwrap();
+                let url = format!("https://example.com/en_US/{}/", name.to_lowercase());
+                (recipe.id, LinkPartial::new(&url, &name))
+            })
+            .collect();
+        let partial = menu
+            .to_partial(
+                "en_US",
+                &recipes,
+                &links,
+                "18:00",
+                &Catalog::load(Path::new("templates")).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            partial.days.iter().map(|day| day.day).collect::<Vec<_>>(),
+            vec![Some(1), Some(2)]
+        );
+        let first = &partial.days[0];
+        assert_eq!(
+            first
+                .courses
+                .iter()
+                .map(|course| course.recipes[0].link.label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Chili", "Pie"]
+        );
+        assert_eq!(first.courses[1].recipes[0].servings, Some(8));
+        assert_eq!(
+            first
+                .timeline
+                .iter()
+                .map(|row| (row.start.as_str(), row.recipe.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("16:30", "Chili"), ("17:15", "Pie")]
+        );
+        assert_eq!(
+            first.timeline[0].url,
+            "https://example.com/en_US/chili/#stage-1"
+        );
+        assert_eq!(
+            partial.shopping_list[0].items,
+            vec![String::from("3 cups onions")]
+        );
+        assert_eq!(menu.equipment("en_US", &recipes).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn menu_err() {
+        let recipes = vec![recipe("Chili", 90, &[]), recipe("Pie", 45, &[])];
+        let taxonomy = Taxonomy::default();
+
+        let mut unknown = menu(&recipes);
+        unknown.entries[0].recipe = Uuid::nil();
+        assert_eq!(
+            unknown
+                .validate(&recipes, &taxonomy)
+                .unwrap_err()
+                .to_string(),
+            "unknown recipe 00000000-0000-0000-0000-000000000000"
+        );
+
+        let mut translated = menu(&recipes);
+        translated.locales.push(String::from("fr_FR"));
+        translated.name = LocalizedString {
+            inner: HashMap::from([
+                (String::from("en_US"), String::from("Dinners")),
+                (String::from("fr_FR"), String::from("Dîners")),
+            ]),
+        };
+        translated.slug = translated.name.clone();
+        assert_eq!(
+            translated
+                .validate(&recipes, &taxonomy)
+                .unwrap_err()
+                .to_string(),
+            format!("recipe {} is not published in fr_FR", recipes[0].id)
+        );
+
+        let mut categorized = menu(&recipes);
+        categorized.category = Some(String::from("holiday"));
+        assert_eq!(
+            categorized
+                .validate(&recipes, &taxonomy)
+                .unwrap_err()
+                .to_string(),
+            "unknown category holiday"
+        );
+
+        assert!(serde_yaml::from_str::<MenuEntry>(
+            "recipe: 7d0ac5a7-2a5b-4e5e-9f3c-5a3b1c2d4e5f\ncourse: brunch"
+        )
+        .is_err());
+    }
+}
@@ -1,33 +1,27 @@
 use anyhow::anyhow;
-use humantime::format_duration;
-use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::de::{Deserialize, DeserializeOwned, Deserializer, MapAccess, Visitor};
 use serde::ser::{Serialize, Serializer};
 use serde::{Deserialize as DeserializeMacro, Serialize as SerializeMacro};
 use slugify::slugify;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::fs;
+use std::marker::PhantomData;
+use std::path::Path;
 use std::time::Duration;
 use uuid::Uuid;
 
+use crate::config::{Author, CalendarConfig, Config, Features, Social};
+use crate::ingredient::{Ingredient, Quantity};
+use crate::locale::{fallback_chain, lookup, lookup_or_default};
+use crate::schedule::Schedule;
+use crate::step::{DurationRange, Temperature};
+use crate::taxonomy::{Taxonomy, TermKind};
 use crate::when::duration_iso8601;
 
 pub const US_ENGLISH: &str = "en_US";
 
-pub const CATEGORIES: &[&str; 13] = &[
-    "breakfast",
-    "lunch",
-    "beverage",
-    "cocktail",
-    "appetizer",
-    "soup",
-    "salad",
-    "main dish",
-    "side dish",
-    "dessert",
-    "break",
-    "holiday",
-    "entertaining",
-];
+pub const IMAGE_MANIFEST: &str = "images.json";
 
 #[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
 pub struct Recipe {
@@ -37,30 +31,329 @@ pub struct Recipe {
     pub published: String,
     pub name: LocalizedString,
     pub slug: LocalizedString,
-    pub category: LocalizedString,
-    pub cuisine: LocalizedString,
+    /// A category id from the taxonomy.
+    pub category: String,
+    /// A cuisine id from the taxonomy.
+    pub cuisine: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<LocalizedString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub keywords: Option<Vec<LocalizedString>>,
-    pub ingredients: Vec<LocalizedString>,
+    pub ingredients: Vec<IngredientLine>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub equipment: Option<Vec<LocalizedString>>,
     pub stages: Vec<Stage>,
+    /// The number of servings the recipe makes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub servings: Option<u32>,
+    /// The author of the recipe, when not one of the site authors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video: Option<Video>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nutrition: Option<Nutrition>,
+}
+
+/// A video of the recipe being made, published in its structured data.
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct Video {
+    pub name: LocalizedString,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<LocalizedString>,
+    pub thumbnail_url: String,
+    pub upload_date: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embed_url: Option<String>,
+    #[serde(default)]
+    #[serde(with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<Duration>,
+}
+
+/// Nutrition facts per serving. Energy is in calories, sodium and cholesterol
+/// in milligrams and everything else in grams.
+#[derive(Debug, Clone, Default, PartialEq, SerializeMacro, DeserializeMacro)]
+#[serde(deny_unknown_fields)]
+pub struct Nutrition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calories: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fat: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saturated_fat: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cholesterol: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sodium: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carbohydrate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fiber: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sugar: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protein: Option<f64>,
 }
 
 #[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
 pub struct Stage {
+    /// The anchor of the stage, when it should not change as stages are added
+    /// or moved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
     pub name: LocalizedString,
 
     #[serde(default)]
     #[serde(with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cook_time: Option<Duration>,
 
     #[serde(default)]
     #[serde(with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub prep_time: Option<Duration>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<LocalizedString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub footer: Option<LocalizedString>,
-    pub steps: Vec<LocalizedString>,
+    pub steps: Vec<Step>,
+
+    /// The ids of the stages that must be done before this one starts. A
+    /// stage without it starts after the previous stage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Vec<String>>,
+
+    /// Whether the stage needs no attention, such as dough rising, so that
+    /// the cook can work on other stages meanwhile.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub hands_off: bool,
+
+    /// The id of the recipe the stage is made by following.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipe: Option<Uuid>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// A value written either as its text, a string or a map of translations, or
+/// as a map of fields that holds the text under `text`.
+trait TextOrFields: Sized {
+    /// The fields of the value. Unknown fields must be denied so that a
+    /// misspelled field is an error rather than a translation.
+    type Fields: DeserializeOwned + Into<Self>;
+    /// The names of the fields, which tell fields from the locales of
+    /// translations.
+    const FIELDS: &'static [&'static str];
+    /// What the value is, for error messages.
+    const NAME: &'static str;
+
+    fn from_text(text: LocalizedString) -> Self;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TextOrFieldsVisitor<T>(PhantomData<T>);
+
+impl<'de, T: TextOrFields> Visitor<'de> for TextOrFieldsVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "en_US string, map of translations or map of {} fields",
+            T::NAME
+        )
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(T::from_text(LocalizedString::new(v)))
+    }
+
+    /// A map is the fields when it has any of them, and the translations of
+    /// the text, keyed by locale, otherwise.
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = serde_yaml::Mapping::new();
+        while let Some((key, value)) = map.next_entry::<String, serde_yaml::Value>()? {
+            entries.insert(serde_yaml::Value::String(key), value);
+        }
+        let has_fields = T::FIELDS
+            .iter()
+            .any(|field| entries.contains_key(&serde_yaml::Value::from(*field)));
+
+        let entries = serde_yaml::Value::Mapping(entries);
+        match has_fields {
+            true => T::Fields::deserialize(entries).map(Into::into),
+            false => LocalizedString::deserialize(entries).map(T::from_text),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+/// An ingredient of a recipe, written as its text or as a map with the `text`
+/// and the `recipe` id of another recipe the ingredient is made by following.
+#[derive(Debug, Clone, PartialEq, SerializeMacro)]
+#[serde(into = "IngredientFormat")]
+pub struct IngredientLine {
+    pub text: LocalizedString,
+    pub recipe: Option<Uuid>,
+}
+
+#[derive(Clone, SerializeMacro)]
+#[serde(untagged)]
+enum IngredientFormat {
+    Fields(IngredientFields),
+    Text(LocalizedString),
+}
+
+#[derive(Clone, SerializeMacro, DeserializeMacro)]
+#[serde(deny_unknown_fields)]
+struct IngredientFields {
+    text: LocalizedString,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recipe: Option<Uuid>,
+}
+
+impl TextOrFields for IngredientLine {
+    type Fields = IngredientFields;
+    const FIELDS: &'static [&'static str] = &["text", "recipe"];
+    const NAME: &'static str = "ingredient";
+
+    fn from_text(text: LocalizedString) -> Self {
+        IngredientLine::from_text(text)
+    }
+}
+
+impl<'de> Deserialize<'de> for IngredientLine {
+    fn deserialize<D>(deserializer: D) -> Result<IngredientLine, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(TextOrFieldsVisitor(PhantomData))
+    }
+}
+
+impl From<IngredientFields> for IngredientLine {
+    fn from(fields: IngredientFields) -> Self {
+        IngredientLine {
+            text: fields.text,
+            recipe: fields.recipe,
+        }
+    }
+}
+
+impl From<IngredientLine> for IngredientFormat {
+    fn from(line: IngredientLine) -> Self {
+        match line.recipe {
+            Some(_) => IngredientFormat::Fields(IngredientFields {
+                text: line.text,
+                recipe: line.recipe,
+            }),
+            None => IngredientFormat::Text(line.text),
+        }
+    }
+}
+
+impl IngredientLine {
+    pub fn new(text: &str) -> Self {
+        IngredientLine::from_text(LocalizedString::new(text))
+    }
+
+    pub fn from_text(text: LocalizedString) -> Self {
+        IngredientLine { text, recipe: None }
+    }
+}
+
+/// A step of a stage, written as its text or as a map with the `text` and
+/// optional structured values: an `id` that keeps the anchor of the step when
+/// steps are added or moved, how long the step takes, its temperature and the
+/// recipe equipment it uses.
+#[derive(Debug, Clone, PartialEq, SerializeMacro)]
+#[serde(into = "StepFormat")]
+pub struct Step {
+    pub id: Option<String>,
+    pub text: LocalizedString,
+    pub duration: Option<DurationRange>,
+    pub temperature: Option<Temperature>,
+    pub equipment: Vec<LocalizedString>,
+}
+
+#[derive(Clone, SerializeMacro)]
+#[serde(untagged)]
+enum StepFormat {
+    Fields(StepFields),
+    Text(LocalizedString),
+}
+
+#[derive(Clone, SerializeMacro, DeserializeMacro)]
+#[serde(deny_unknown_fields)]
+struct StepFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    text: LocalizedString,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration: Option<DurationRange>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    temperature: Option<Temperature>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    equipment: Vec<LocalizedString>,
+}
+
+impl TextOrFields for Step {
+    type Fields = StepFields;
+    const FIELDS: &'static [&'static str] = &["id", "text", "duration", "temperature", "equipment"];
+    const NAME: &'static str = "step";
+
+    fn from_text(text: LocalizedString) -> Self {
+        Step::from_text(text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Step {
+    fn deserialize<D>(deserializer: D) -> Result<Step, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(TextOrFieldsVisitor(PhantomData))
+    }
+}
+
+impl From<StepFields> for Step {
+    fn from(fields: StepFields) -> Self {
+        Step {
+            id: fields.id,
+            text: fields.text,
+            duration: fields.duration,
+            temperature: fields.temperature,
+            equipment: fields.equipment,
+        }
+    }
+}
+
+impl From<Step> for StepFormat {
+    fn from(step: Step) -> Self {
+        match step.id.is_some() || step.has_details() {
+            true => StepFormat::Fields(StepFields {
+                id: step.id,
+                text: step.text,
+                duration: step.duration,
+                temperature: step.temperature,
+                equipment: step.equipment,
+            }),
+            false => StepFormat::Text(step.text),
+        }
+    }
 }
 
 impl fmt::Display for Recipe {
@@ -75,6 +368,8 @@ impl Recipe {
         locale: Option<String>,
         allowed_locales: &[String],
         images: Vec<(String, String)>,
+        image_details: Option<&ImageDetails>,
+        taxonomy: &Taxonomy,
     ) -> Result<RecipePartial, anyhow::Error> {
         let cook_time: Duration = self
             .stages
@@ -90,7 +385,31 @@ impl Recipe {
             .fold(Duration::new(0, 0), |sum, val| {
                 sum + val.prep_time.unwrap_or_default()
             });
-        let total_time = cook_time + prep_time;
+        let schedule = Schedule::new(self)?;
+        let total_time = schedule.total;
+        let locale_id = locale.clone().unwrap_or_else(|| US_ENGLISH.to_string());
+        let format_locale = lookup_or_default(&locale_id);
+
+        let mut scheduled_stages: Vec<ScheduledStagePartial> = Vec::new();
+        for scheduled in &schedule.stages {
+            let duration = scheduled.end - scheduled.start;
+            scheduled_stages.push(ScheduledStagePartial {
+                id: scheduled.anchor.clone(),
+                name: self.stages[scheduled.index]
+                    .name
+                    .localized(locale.clone())?,
+                start: match scheduled.start.is_zero() {
+                    false => Some(format_locale.format_duration(scheduled.start)),
+                    true => None,
+                },
+                start_seconds: scheduled.start.as_secs(),
+                duration: match duration.is_zero() {
+                    false => Some(format_locale.format_duration(duration)),
+                    true => None,
+                },
+                hands_off: scheduled.hands_off,
+            });
+        }
 
         Ok(RecipePartial {
             id: self.id,
@@ -98,19 +417,19 @@ impl Recipe {
                 .locales
                 .clone()
                 .into_iter()
-                .take_while(|e| allowed_locales.contains(e))
+                .filter(|e| allowed_locales.contains(e))
                 .map(|l| (l.clone(), self.slug.clone().localized(Some(l)).unwrap()))
                 .collect(),
             cook_time: match cook_time.is_zero() {
-                false => Some(format_duration(cook_time).to_string()),
+                false => Some(lookup_or_default(&locale_id).format_duration(cook_time)),
                 true => None,
             },
             prep_time: match prep_time.is_zero() {
-                false => Some(format_duration(prep_time).to_string()),
+                false => Some(lookup_or_default(&locale_id).format_duration(prep_time)),
                 true => None,
             },
             total_time: match total_time.is_zero() {
-                false => Some(format_duration(total_time).to_string()),
+                false => Some(lookup_or_default(&locale_id).format_duration(total_time)),
                 true => None,
             },
             sd_cook_time: match cook_time.is_zero() {
@@ -127,13 +446,18 @@ impl Recipe {
             },
             name: self.name.clone().localized(locale.clone())?,
             published: self.published.clone(),
+            published_label: match lookup(&locale.clone().unwrap_or_else(|| US_ENGLISH.to_string()))
+            {
+                Some(registered) => registered.format_date(&self.published),
+                None => self.published.clone(),
+            },
             slug: self.slug.clone().localized(locale.clone())?,
             description: match self.description.clone() {
                 Some(x) => Some(x.localized(locale.clone())?),
                 None => None,
             },
-            category: self.category.clone().localized(locale.clone())?,
-            cuisine: self.cuisine.clone().localized(locale.clone())?,
+            category: taxonomy.label(TermKind::Category, &self.category, locale.clone()),
+            cuisine: taxonomy.label(TermKind::Cuisine, &self.cuisine, locale.clone()),
             keywords: match &self.keywords {
                 None => Vec::new(),
                 Some(keywords) => keywords
@@ -141,21 +465,140 @@ impl Recipe {
                     .map(|kw| kw.localized(locale.clone()).unwrap_or_default())
                     .collect(),
             },
-            ingredients: localied_vec(&self.ingredients, locale.clone())?,
+            ingredients: localied_vec(
+                &self
+                    .ingredients
+                    .iter()
+                    .map(|line| line.text.clone())
+                    .collect::<Vec<LocalizedString>>(),
+                locale.clone(),
+            )?,
             equipment: match &self.equipment {
                 None => Vec::new(),
                 Some(equipment) => localied_vec(equipment, locale.clone())?,
             },
             stages: self
                 .stages
-                .clone()
-                .into_iter()
-                .map(|x| x.to_partial(locale.clone()).unwrap())
-                .collect(),
+                .iter()
+                .enumerate()
+                .map(|(index, stage)| stage.to_partial(index, locale.clone()))
+                .collect::<Result<Vec<StagePartial>, anyhow::Error>>()?,
+            schedule: scheduled_stages,
+            schedule_seconds: total_time.as_secs(),
             images,
+            image_color: image_details.map(|details| details.color.clone()),
+            image_placeholder: image_details.map(|details| details.placeholder.clone()),
         })
     }
 
+    /// The file name of the generated Open Graph share card for a locale.
+    pub fn share_card_file(&self, locale: &str) -> String {
+        format!("{}_{}_card.jpg", self.id, locale)
+    }
+
+    /// The anchors of the stages and steps of the recipe, in page order.
+    pub fn anchors(&self) -> Vec<String> {
+        let mut anchors: Vec<String> = Vec::new();
+        for (index, stage) in self.stages.iter().enumerate() {
+            let stage_anchor = stage.anchor(index);
+            anchors.push(stage_anchor.clone());
+            anchors.extend(
+                stage
+                    .steps
+                    .iter()
+                    .enumerate()
+                    .map(|(step_index, step)| step.anchor(&stage_anchor, step_index)),
+            );
+        }
+        anchors
+    }
+
+    /// The ids of the recipes the ingredients and stages of the recipe are
+    /// made by following, without duplicates.
+    pub fn sub_recipes(&self) -> Vec<Uuid> {
+        let mut ids: Vec<Uuid> = Vec::new();
+        let references = self
+            .ingredients
+            .iter()
+            .filter_map(|line| line.recipe)
+            .chain(self.stages.iter().filter_map(|stage| stage.recipe));
+        for id in references {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    /// The ingredient lines to buy for the recipe, each with the amount of it
+    /// to scale the line by: its own lines and, in place of the lines made by
+    /// following another recipe, the lines of that recipe. A sub-recipe is
+    /// made as many times as the quantity of the ingredient lines that use it,
+    /// such as twice for "2 pie crusts", and once when it is only used by a
+    /// stage.
+    pub fn ingredient_lines<'a>(
+        &'a self,
+        recipes: &'a [Recipe],
+    ) -> Result<Vec<(&'a IngredientLine, f64)>, anyhow::Error> {
+        let mut lines: Vec<(&IngredientLine, f64)> = Vec::new();
+        self.push_ingredient_lines(recipes, 1.0, &mut vec![self], &mut lines)?;
+        Ok(lines)
+    }
+
+    fn push_ingredient_lines<'a>(
+        &'a self,
+        recipes: &'a [Recipe],
+        scale: f64,
+        path: &mut Vec<&'a Recipe>,
+        lines: &mut Vec<(&'a IngredientLine, f64)>,
+    ) -> Result<(), anyhow::Error> {
+        lines.extend(
+            self.ingredients
+                .iter()
+                .filter(|line| line.recipe.is_none())
+                .map(|line| (line, scale)),
+        );
+        for id in self.sub_recipes() {
+            let sub_recipe = recipes
+                .iter()
+                .find(|recipe| recipe.id == id)
+                .ok_or_else(|| anyhow!("{} is made with unknown recipe {}", self, id))?;
+            path.push(sub_recipe);
+            if path[..path.len() - 1].iter().any(|recipe| recipe.id == id) {
+                let names = path
+                    .iter()
+                    .map(|recipe| recipe.name.localized(None))
+                    .collect::<Result<Vec<String>, anyhow::Error>>()?;
+                return Err(anyhow!("recipe cycle {}", names.join(" -> ")));
+            }
+            let mut batches: Option<f64> = None;
+            for line in self
+                .ingredients
+                .iter()
+                .filter(|line| line.recipe == Some(id))
+            {
+                let quantity = match Ingredient::parse(&line.text.localized(None)?).quantity {
+                    Some(Quantity {
+                        amount,
+                        max: Some(max),
+                    }) => (amount + max) / 2.0,
+                    Some(quantity) => quantity.amount,
+                    None => 1.0,
+                };
+                *batches.get_or_insert(0.0) += quantity;
+            }
+
+            sub_recipe.push_ingredient_lines(
+                recipes,
+                scale * batches.unwrap_or(1.0),
+                path,
+                lines,
+            )?;
+            path.pop();
+        }
+        Ok(())
+    }
+
     pub fn init(arg_recipe_id: Option<Uuid>, arg_name: Option<String>, mock: bool) -> Self {
         let recipe_id: Uuid = match arg_recipe_id {
             Some(value) => value,
@@ -170,7 +613,6 @@ impl Recipe {
             .to_string()
             .chars()
             .rev()
-            .into_iter()
             .take(12)
             .collect::<String>()
             .chars()
@@ -178,21 +620,19 @@ impl Recipe {
             .collect();
         let slug: String = slugify!(format!("{}-{}", short_id, name).as_str());
         let description: Option<LocalizedString> = match mock {
-            true => Some(LocalizedString::new(
-                &"This recipe is pretty neat.".to_string(),
-            )),
+            true => Some(LocalizedString::new("This recipe is pretty neat.")),
             false => None,
         };
-        let ingredients: Vec<LocalizedString> = match mock {
+        let ingredients: Vec<IngredientLine> = match mock {
             true => vec![
-                LocalizedString::new(&"celery".to_string()),
-                LocalizedString::new(&"onion".to_string()),
-                LocalizedString::new(&"bell pepper".to_string()),
+                IngredientLine::new("celery"),
+                IngredientLine::new("onion"),
+                IngredientLine::new("bell pepper"),
             ],
             false => Vec::new(),
         };
         let equipment: Vec<LocalizedString> = match mock {
-            true => vec![LocalizedString::new(&"dutch oven".to_string())],
+            true => vec![LocalizedString::new("dutch oven")],
             false => Vec::new(),
         };
         let stages: Vec<Stage> = match mock {
@@ -205,36 +645,81 @@ impl Recipe {
             published: String::from("2022-01-01"),
             name: LocalizedString::new(&name),
             slug: LocalizedString::new(&slug),
-            category: LocalizedString::new("Dinner"),
-            cuisine: LocalizedString::new("American"),
+            category: String::from("main-dish"),
+            cuisine: String::from("american"),
             keywords: Some(vec![LocalizedString::new("favorite")]),
             description,
 
             ingredients,
             equipment: Some(equipment),
             stages,
+            servings: None,
+            author: None,
+            video: None,
+            nutrition: None,
         }
     }
 }
 
 impl Stage {
-    pub fn to_partial(&self, locale: Option<String>) -> Result<StagePartial, anyhow::Error> {
+    /// The anchor of the stage at a position in the recipe, `stage-2` for the
+    /// second stage unless it has an id.
+    pub fn anchor(&self, index: usize) -> String {
+        match &self.id {
+            Some(id) => id.clone(),
+            None => format!("stage-{}", index + 1),
+        }
+    }
+
+    pub fn to_partial(
+        &self,
+        index: usize,
+        locale: Option<String>,
+    ) -> Result<StagePartial, anyhow::Error> {
         let cook_time = self.cook_time.unwrap_or_default();
         let prep_time = self.prep_time.unwrap_or_default();
         let total_time = cook_time + prep_time;
+        let locale_id = locale.clone().unwrap_or_else(|| US_ENGLISH.to_string());
+
+        let id = self.anchor(index);
+
+        let step_locale = lookup_or_default(&locale_id);
+        let mut steps: Vec<StepPartial> = Vec::with_capacity(self.steps.len());
+        for (step_index, step) in self.steps.iter().enumerate() {
+            let (duration, max_duration) = match &step.duration {
+                Some(duration) => duration.localized(step_locale),
+                None => (String::new(), None),
+            };
+            steps.push(StepPartial {
+                id: step.anchor(&id, step_index),
+                text: step.text.localized(locale.clone())?,
+                duration: step.duration.map(|_| duration),
+                max_duration,
+                timer_seconds: step.duration.map(|duration| duration.min.as_secs()),
+                timer_max_seconds: step
+                    .duration
+                    .and_then(|duration| duration.max)
+                    .map(|max| max.as_secs()),
+                temperature: step
+                    .temperature
+                    .map(|temperature| temperature.localized(step_locale)),
+                equipment: localied_vec(&step.equipment, locale.clone())?,
+            });
+        }
 
         Ok(StagePartial {
+            id,
             name: self.name.clone().localized(locale.clone())?,
             cook_time: match cook_time.is_zero() {
-                false => Some(format_duration(cook_time).to_string()),
+                false => Some(lookup_or_default(&locale_id).format_duration(cook_time)),
                 true => None,
             },
             prep_time: match prep_time.is_zero() {
-                false => Some(format_duration(prep_time).to_string()),
+                false => Some(lookup_or_default(&locale_id).format_duration(prep_time)),
                 true => None,
             },
             total_time: match total_time.is_zero() {
-                false => Some(format_duration(total_time).to_string()),
+                false => Some(lookup_or_default(&locale_id).format_duration(total_time)),
                 true => None,
             },
             description: match self.description.clone() {
@@ -245,21 +730,53 @@ impl Stage {
                 Some(x) => Some(x.localized(locale.clone())?),
                 None => None,
             },
-            steps: localied_vec(&self.steps, locale)?,
+            steps,
+            recipe: None,
         })
     }
 
     pub fn init(name: String) -> Self {
         Stage {
+            id: None,
             name: LocalizedString::new(&name),
             cook_time: None,
             prep_time: None,
             description: None,
             footer: None,
-            steps: vec![
-                LocalizedString::new(&"First do this".to_string()),
-                LocalizedString::new(&"Then do that".to_string()),
-            ],
+            steps: vec![Step::new("First do this"), Step::new("Then do that")],
+            after: None,
+            hands_off: false,
+            recipe: None,
+        }
+    }
+}
+
+impl Step {
+    pub fn new(text: &str) -> Self {
+        Step::from_text(LocalizedString::new(text))
+    }
+
+    pub fn from_text(text: LocalizedString) -> Self {
+        Step {
+            id: None,
+            text,
+            duration: None,
+            temperature: None,
+            equipment: Vec::new(),
+        }
+    }
+
+    /// Whether the step has a duration, temperature or equipment.
+    pub fn has_details(&self) -> bool {
+        self.duration.is_some() || self.temperature.is_some() || !self.equipment.is_empty()
+    }
+
+    /// The anchor of the step at a position in its stage, `stage-1-step-3` for
+    /// the third step of the first stage unless it has an id.
+    pub fn anchor(&self, stage_anchor: &str, index: usize) -> String {
+        match &self.id {
+            Some(id) => id.clone(),
+            None => format!("{}-step-{}", stage_anchor, index + 1),
         }
     }
 }
@@ -270,6 +787,7 @@ pub struct RecipePartial {
     pub alternate_locales: Vec<(String, String)>,
     pub name: String,
     pub published: String,
+    pub published_label: String,
     pub slug: String,
     pub description: Option<String>,
     pub category: String,
@@ -278,6 +796,9 @@ pub struct RecipePartial {
     pub ingredients: Vec<String>,
     pub equipment: Vec<String>,
     pub stages: Vec<StagePartial>,
+    /// The stages in the order they start.
+    pub schedule: Vec<ScheduledStagePartial>,
+    pub schedule_seconds: u64,
     pub cook_time: Option<String>,
     pub prep_time: Option<String>,
     pub total_time: Option<String>,
@@ -285,41 +806,72 @@ pub struct RecipePartial {
     pub sd_prep_time: Option<String>,
     pub sd_total_time: Option<String>,
     pub images: Vec<(String, String)>,
-}
-
-impl RecipePartial {
-    pub fn flat_steps(&self) -> Vec<String> {
-        let size = (&self.stages).iter().map(|s| s.steps.len()).sum();
-        let mut all_steps = Vec::with_capacity(size);
-        for stage in &self.stages {
-            all_steps.extend(stage.steps.clone());
-        }
-        all_steps
-    }
+    pub image_color: Option<String>,
+    pub image_placeholder: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
 pub struct StagePartial {
+    pub id: String,
     pub name: String,
     pub cook_time: Option<String>,
     pub prep_time: Option<String>,
     pub total_time: Option<String>,
     pub description: Option<String>,
     pub footer: Option<String>,
-    pub steps: Vec<String>,
+    pub steps: Vec<StepPartial>,
+    /// The name and URL of the recipe the stage is made by following.
+    pub recipe: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct ScheduledStagePartial {
+    pub id: String,
+    pub name: String,
+    /// How long after the start of the recipe the stage starts.
+    pub start: Option<String>,
+    pub start_seconds: u64,
+    pub duration: Option<String>,
+    pub hands_off: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct StepPartial {
+    pub id: String,
+    pub text: String,
+    pub duration: Option<String>,
+    pub max_duration: Option<String>,
+    pub timer_seconds: Option<u64>,
+    pub timer_max_seconds: Option<u64>,
+    pub temperature: Option<String>,
+    pub equipment: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
 pub struct SiteView {
     pub public_url: String,
     pub version: String,
+    pub name: String,
+    pub repository: Option<String>,
+    pub authors: Vec<Author>,
+    pub social: Social,
+    pub default_locale: String,
+    pub features: Features,
+    pub calendar: CalendarConfig,
 }
 
 impl SiteView {
-    pub fn new(public_url: &str, version: &str) -> Self {
+    pub fn new(public_url: &str, version: &str, config: &Config) -> Self {
         SiteView {
             public_url: public_url.to_string(),
             version: version.to_string(),
+            name: config.site.name.clone(),
+            repository: config.site.repository.clone(),
+            authors: config.site.authors.clone(),
+            social: config.site.social.clone(),
+            default_locale: config.locales.default.clone(),
+            features: config.features.clone(),
+            calendar: config.calendar.clone(),
         }
     }
 }
@@ -330,6 +882,7 @@ pub struct HomeView {
     pub title: String,
     pub site: SiteView,
     pub self_url: String,
+    pub alternates: Vec<(String, String)>,
 }
 
 #[derive(Debug, PartialEq, SerializeMacro, DeserializeMacro)]
@@ -347,10 +900,92 @@ pub struct RecipeView {
     pub title: String,
     pub recipe: RecipePartial,
     pub site: SiteView,
-    pub flat_steps: Vec<String>,
+    /// The schema.org Recipe of the page as JSON-LD.
+    pub structured_data: String,
     pub self_url: String,
-    pub oembed_url: String,
+    pub oembed_url: Option<String>,
+    /// The cooking schedule calendar of the recipe.
+    pub calendar_url: Option<String>,
+    /// When the published calendar serves, such as `2000-01-01T18:00`, for
+    /// the page to move its events to the day and time picked by the reader.
+    pub calendar_serve: Option<String>,
     pub meta: Vec<(String, String)>,
+    pub alternates: Vec<(String, String)>,
+    /// The ingredients of the recipe and the URLs of the recipes they are
+    /// made by following.
+    pub ingredients: Vec<(String, String)>,
+    /// The keywords of the recipe and the URLs of their tag pages.
+    pub keywords: Vec<(String, String)>,
+    /// The equipment of the recipe and the URLs of their tag pages.
+    pub equipment: Vec<(String, String)>,
+    /// The nutrients of a serving of the recipe and their amounts.
+    pub nutrition: Vec<(String, String)>,
+}
+
+#[derive(Debug, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct MenuView {
+    pub locale: String,
+    pub title: String,
+    pub menu: MenuPartial,
+    pub site: SiteView,
+    pub self_url: String,
+    pub alternates: Vec<(String, String)>,
+    /// The category of the menu and the URL of its page.
+    pub category: Option<(String, String)>,
+    /// The equipment of the recipes of the menu and the URLs of their tag
+    /// pages.
+    pub equipment: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct MenuPartial {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub description: Option<String>,
+    /// The time every recipe of the menu is ready at, as `HH:MM`.
+    pub serve_at: String,
+    pub days: Vec<MenuDayPartial>,
+    pub shopping_list: Vec<ShoppingSectionPartial>,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct MenuDayPartial {
+    /// The day of a meal plan, or none for a single meal.
+    pub day: Option<u32>,
+    pub courses: Vec<MenuCoursePartial>,
+    /// The stages of every recipe of the day in the order they start.
+    pub timeline: Vec<TimelinePartial>,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct MenuCoursePartial {
+    pub name: Option<String>,
+    pub recipes: Vec<MenuRecipePartial>,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct MenuRecipePartial {
+    pub link: LinkPartial,
+    pub servings: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct TimelinePartial {
+    /// The clock time the stage starts at, as `HH:MM`.
+    pub start: String,
+    pub recipe: String,
+    pub stage: String,
+    /// The URL of the stage on the recipe page.
+    pub url: String,
+    pub duration: Option<String>,
+    pub hands_off: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct ShoppingSectionPartial {
+    pub name: String,
+    pub items: Vec<String>,
 }
 
 #[derive(Debug, PartialEq, SerializeMacro, DeserializeMacro)]
@@ -370,18 +1005,120 @@ pub struct SearchView {
 
 #[derive(Debug, PartialEq, SerializeMacro, DeserializeMacro)]
 pub struct SiteMapView {
-    pub links: Vec<String>,
+    pub links: Vec<SiteMapLinkPartial>,
     pub site: SiteView,
 }
 
+#[derive(Debug, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct SiteMapLinkPartial {
+    pub url: String,
+    /// The `(hreflang, url)` language versions of the page.
+    pub alternates: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct LinkPartial {
+    pub url: String,
+    pub label: String,
+    pub thumbnail_url: Option<String>,
+    pub image_color: Option<String>,
+    pub image_placeholder: Option<String>,
+    /// The number of recipes behind the link, shown on tag indexes.
+    pub count: Option<usize>,
+}
+
+impl LinkPartial {
+    pub fn new(url: &str, label: &str) -> Self {
+        LinkPartial {
+            url: url.to_string(),
+            label: label.to_string(),
+            thumbnail_url: None,
+            image_color: None,
+            image_placeholder: None,
+            count: None,
+        }
+    }
+
+    pub fn recipe(url: &str, recipe: &RecipePartial, thumbnail_url: Option<String>) -> Self {
+        LinkPartial {
+            url: url.to_string(),
+            label: recipe.name.clone(),
+            thumbnail_url,
+            image_color: recipe.image_color.clone(),
+            image_placeholder: recipe.image_placeholder.clone(),
+            count: None,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, SerializeMacro, DeserializeMacro)]
 pub struct LinkListView {
     pub locale: String,
     pub title: String,
     pub links_label: String,
-    pub links: Vec<(String, String)>,
+    /// Introduces the links, such as the description of a taxonomy term.
+    pub preface: Option<String>,
+    pub links: Vec<LinkPartial>,
     pub site: SiteView,
     pub self_url: String,
+    pub alternates: Vec<(String, String)>,
+}
+
+#[derive(Debug, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct LetterIndexView {
+    pub locale: String,
+    pub title: String,
+    pub links_label: String,
+    pub letters: Vec<LetterPartial>,
+    pub site: SiteView,
+    pub self_url: String,
+    pub alternates: Vec<(String, String)>,
+}
+
+/// The recipes listed under a letter of an alphabetical index.
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct LetterPartial {
+    pub letter: String,
+    pub anchor: String,
+    pub links: Vec<LinkPartial>,
+}
+
+/// Image details computed by `jrb convert` and stored in the image manifest
+/// next to the recipe files.
+#[derive(Debug, Clone, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct ImageDetails {
+    pub width: u32,
+    pub height: u32,
+    /// The dominant color of the image as a `#rrggbb` hex string.
+    pub color: String,
+    /// A tiny base64 encoded `data:` URI of the image.
+    pub placeholder: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, SerializeMacro, DeserializeMacro)]
+pub struct ImageManifest {
+    pub images: BTreeMap<Uuid, ImageDetails>,
+    /// A hash of what each share card is rendered from, keyed by the card
+    /// file name, so that cards are only rendered again when it changes.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub share_cards: BTreeMap<String, String>,
+}
+
+impl ImageManifest {
+    pub fn load(recipe_dir: &Path) -> Result<Self, anyhow::Error> {
+        let manifest_path = recipe_dir.join(IMAGE_MANIFEST);
+        if !manifest_path.exists() {
+            return Ok(ImageManifest::default());
+        }
+        let manifest_json = fs::read_to_string(&manifest_path)?;
+        Ok(serde_json::from_str(&manifest_json)?)
+    }
+
+    pub fn save(&self, recipe_dir: &Path) -> Result<(), anyhow::Error> {
+        let manifest_json = serde_json::to_string_pretty(self)?;
+        fs::write(recipe_dir.join(IMAGE_MANIFEST), manifest_json)?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -390,15 +1127,16 @@ pub struct LocalizedString {
 }
 
 impl LocalizedString {
+    /// Get the value for a locale, following the locale's fallback chain
+    /// when there is no translation.
     pub fn localized(&self, locale: Option<String>) -> Result<String, anyhow::Error> {
         let search_locale = locale.unwrap_or_else(|| US_ENGLISH.to_string());
-        if let Some(value) = self.inner.get(&search_locale) {
-            return Ok(value.to_string());
-        }
-        if let Some(value) = self.inner.get(&US_ENGLISH.to_string()) {
-            return Ok(value.to_string());
+        for candidate in fallback_chain(&search_locale) {
+            if let Some(value) = self.inner.get(candidate) {
+                return Ok(value.to_string());
+            }
         }
-        return Err(anyhow!("Missing locale: {}", search_locale));
+        Err(anyhow!("Missing locale: {}", search_locale))
     }
 
     pub fn new(value: &str) -> Self {
@@ -419,7 +1157,6 @@ impl fmt::Display for LocalizedString {
             self.inner
                 .clone()
                 .into_iter()
-                .into_iter()
                 .map(|(key, value)| format!("{}={}", key, value))
                 .collect::<Vec<String>>()
                 .join(";")
@@ -432,7 +1169,12 @@ impl Serialize for LocalizedString {
     where
         S: Serializer,
     {
-        serializer.collect_map(self.inner.iter())
+        // Untranslated strings are written as plain strings and translated
+        // ones sorted by locale so that recipe files written by jrb are stable.
+        match (self.inner.len(), self.inner.get(US_ENGLISH)) {
+            (1, Some(value)) => serializer.serialize_str(value),
+            _ => serializer.collect_map(self.inner.iter().collect::<BTreeMap<_, _>>()),
+        }
     }
 }
 
@@ -491,6 +1233,16 @@ pub fn localied_vec(
     Ok(results)
 }
 
+/// Check that the recipes every ingredient and stage is made by following
+/// exist and that no recipe is made with itself, directly or through other
+/// recipes.
+pub fn check_sub_recipes(recipes: &[Recipe]) -> Result<(), anyhow::Error> {
+    for recipe in recipes {
+        recipe.ingredient_lines(recipes)?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, PartialEq, SerializeMacro, DeserializeMacro)]
 pub struct OembedView {
     pub locale: String,
@@ -518,3 +1270,126 @@ pub struct OembedJsonView {
     pub width: Option<i32>,
     pub height: Option<i32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stage_anchors_ok() {
+        let stages: Vec<Stage> = serde_yaml::from_str(
+            "
+- name: Prep
+  steps:
+  - Chop the onion.
+  - id: brown-the-meat
+    text:
+      en_US: Brown the meat.
+      fr_FR: Faire dorer la viande.
+- id: sauce
+  name: Sauce
+  steps:
+  - en_US: Simmer.
+",
+        )
+        .unwrap();
+        assert_eq!(stages[0].steps[0], Step::new("Chop the onion."));
+        assert_eq!(stages[0].steps[1].id, Some(String::from("brown-the-meat")));
+        assert_eq!(
+            stages[0].steps[1]
+                .text
+                .localized(Some(String::from("fr_FR")))
+                .unwrap(),
+            "Faire dorer la viande."
+        );
+
+        let mut recipe = Recipe::init(None, None, false);
+        recipe.stages = stages;
+        assert_eq!(
+            recipe.anchors(),
+            vec![
+                "stage-1",
+                "stage-1-step-1",
+                "brown-the-meat",
+                "sauce",
+                "sauce-step-1"
+            ]
+        );
+
+        let yaml = serde_yaml::to_string(&recipe.stages).unwrap();
+        assert!(yaml.contains("- Chop the onion."));
+        assert_eq!(
+            serde_yaml::from_str::<Vec<Stage>>(&yaml).unwrap(),
+            recipe.stages
+        );
+    }
+
+    #[test]
+    fn sub_recipes_ok() {
+        let mut crust = Recipe::init(None, Some(String::from("Pie Crust")), false);
+        crust.ingredients = vec![IngredientLine::new("2 cups flour")];
+        let mut pie = Recipe::init(None, Some(String::from("Apple Pie")), false);
+        pie.ingredients = serde_yaml::from_str(&format!(
+            "
+- text: 1 pie crust
+  recipe: {}
+- en_US: 6 apples
+  fr_FR: 6 pommes
+",
+            crust.id
+        ))
+        .unwrap();
+        pie.stages = vec![Stage::init(String::from("Make the crust"))];
+        pie.stages[0].recipe = Some(crust.id);
+        assert_eq!(pie.ingredients[0].recipe, Some(crust.id));
+        assert_eq!(pie.ingredients[1].recipe, None);
+        assert_eq!(pie.sub_recipes(), vec![crust.id]);
+
+        let yaml = serde_yaml::to_string(&pie.ingredients).unwrap();
+        assert!(yaml.contains(&format!("recipe: {}", crust.id)));
+        assert_eq!(
+            serde_yaml::from_str::<Vec<IngredientLine>>(&yaml).unwrap(),
+            pie.ingredients
+        );
+        assert!(serde_yaml::from_str::<IngredientLine>(&format!("recipe: {}", crust.id)).is_err());
+        assert!(serde_yaml::from_str::<IngredientLine>("text: flour\nfr_FR: farine").is_err());
+
+        let recipes = vec![crust.clone(), pie.clone()];
+        check_sub_recipes(&recipes).unwrap();
+        let lines = |pie: &Recipe, recipes: &[Recipe]| -> Vec<(String, f64)> {
+            pie.ingredient_lines(recipes)
+                .unwrap()
+                .iter()
+                .map(|(line, scale)| (line.text.localized(None).unwrap(), *scale))
+                .collect()
+        };
+        assert_eq!(
+            lines(&pie, &recipes),
+            vec![
+                (String::from("6 apples"), 1.0),
+                (String::from("2 cups flour"), 1.0)
+            ]
+        );
+
+        // A deep dish lattice pie takes three crusts, whatever the servings of
+        // the crust recipe.
+        let mut lattice_pie = pie.clone();
+        lattice_pie.ingredients[0].text = LocalizedString::new("3 pie crusts");
+        assert_eq!(lines(&lattice_pie, &recipes)[1].1, 3.0);
+        crust.servings = Some(2);
+        let recipes = vec![crust.clone(), pie.clone()];
+        assert_eq!(lines(&lattice_pie, &recipes)[1].1, 3.0);
+
+        assert_eq!(
+            check_sub_recipes(&[pie.clone()]).unwrap_err().to_string(),
+            format!("{} is made with unknown recipe {}", pie, crust.id)
+        );
+
+        crust.stages = vec![Stage::init(String::from("Bake"))];
+        crust.stages[0].recipe = Some(pie.id);
+        assert_eq!(
+            check_sub_recipes(&[crust, pie]).unwrap_err().to_string(),
+            "recipe cycle Pie Crust -> Apple Pie -> Pie Crust"
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use anyhow::anyhow;
+use std::fs;
+use std::path::Path;
+
+use crate::ingredient::{best_match, Ingredient};
+use crate::model::{Nutrition, Recipe};
+use crate::shopping::{VOLUMES, WEIGHTS};
+
+/// The default nutrient table, relative to the working directory.
+pub const NUTRIENTS_FILE: &str = "nutrients.csv";
+
+/// The columns of the nutrient table with a nutrient per 100 grams, in the
+/// order of `nutrients`.
+pub const NUTRIENT_COLUMNS: &[&str] = &[
+    "calories",
+    "fat",
+    "saturated_fat",
+    "cholesterol",
+    "sodium",
+    "carbohydrate",
+    "fiber",
+    "sugar",
+    "protein",
+];
+
+/// A food of the nutrient table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Food {
+    /// The singular English name of the food, such as "bell pepper".
+    pub name: String,
+    /// The nutrients in 100 grams of the food.
+    pub nutrition: Nutrition,
+    /// The weight of a cup of the food, to convert volumes.
+    pub grams_per_cup: Option<f64>,
+    /// The weight of one piece of the food as it is counted in recipes, such
+    /// as an egg or a clove of garlic.
+    pub grams_each: Option<f64>,
+}
+
+/// The foods ingredients are matched against to compute nutrition facts,
+/// read from a CSV file with a header row.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NutrientTable {
+    pub foods: Vec<Food>,
+}
+
+/// The nutrition facts of a recipe along with the ingredient lines that could
+/// not be counted in them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipeNutrition {
+    /// The nutrition facts per serving, when the recipe sets them or says how
+    /// many servings it makes and every measured ingredient is counted.
+    pub nutrition: Option<Nutrition>,
+    /// The ingredient lines with no food in the table or an amount that cannot
+    /// be weighed, and why.
+    pub unmatched: Vec<(String, String)>,
+}
+
+impl NutrientTable {
+    pub fn load(nutrients_file: &Path) -> Result<Self, anyhow::Error> {
+        let csv = fs::read_to_string(nutrients_file)
+            .map_err(|err| anyhow!("{}: {}", nutrients_file.display(), err))?;
+        NutrientTable::parse(&csv).map_err(|err| anyhow!("{}: {}", nutrients_file.display(), err))
+    }
+
+    /// Parse the table. The `name` column is required and empty cells or
+    /// missing columns are unknown values.
+    pub fn parse(csv: &str) -> Result<Self, anyhow::Error> {
+        let mut lines = csv
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let header: Vec<String> = match lines.next() {
+            Some((_, line)) => split_row(line),
+            None => return Ok(NutrientTable::default()),
+        };
+        let column = |name: &str| header.iter().position(|column| column == name);
+        let name_column = column("name").ok_or_else(|| anyhow!("missing column name"))?;
+        let nutrient_columns: Vec<Option<usize>> =
+            NUTRIENT_COLUMNS.iter().map(|name| column(name)).collect();
+        let cup_column = column("grams_per_cup");
+        let each_column = column("grams_each");
+
+        let mut foods: Vec<Food> = Vec::new();
+        for (index, line) in lines {
+            let row = split_row(line);
+            let number = |column: Option<usize>| -> Result<Option<f64>, anyhow::Error> {
+                match column.and_then(|column| row.get(column)) {
+                    Some(cell) if !cell.is_empty() => cell
+                        .parse::<f64>()
+                        .map(Some)
+                        .map_err(|_| anyhow!("line {}: invalid number {}", index + 1, cell)),
+                    _ => Ok(None),
+                }
+            };
+
+            let name = row
+                .get(name_column)
+                .map(|name| name.to_lowercase())
+                .unwrap_or_default();
+            if name.is_empty() {
+                return Err(anyhow!("line {}: name cannot be empty", index + 1));
+            }
+            if foods.iter().any(|food| food.name == name) {
+                return Err(anyhow!("line {}: duplicate food {}", index + 1, name));
+            }
+
+            let mut nutrition = Nutrition::default();
+            for (value, column) in nutrients_mut(&mut nutrition)
+                .into_iter()
+                .zip(&nutrient_columns)
+            {
+                *value = number(*column)?;
+            }
+            foods.push(Food {
+                name,
+                nutrition,
+                grams_per_cup: number(cup_column)?,
+                grams_each: number(each_column)?,
+            });
+        }
+        Ok(NutrientTable { foods })
+    }
+
+    /// The food an ingredient name is made of. The longest food name found in
+    /// the ingredient wins, then the one nearest the end, as for store
+    /// sections, and plural words match singular food names.
+    pub fn find(&self, name: &str) -> Option<&Food> {
+        best_match(
+            name,
+            self.foods.iter().map(|food| (food, food.name.as_str())),
+            same_word,
+        )
+    }
+
+    /// The nutrition facts per serving of a recipe, counting the ingredients
+    /// of the recipes it is made with. They are only computed when every
+    /// ingredient measured in a volume or weight is counted, so that a garnish
+    /// such as "1 sprig of mint" missing from the table does not hide them.
+    /// The nutrition set in the recipe takes precedence over the computed
+    /// values it sets.
+    pub fn recipe_nutrition(
+        &self,
+        recipe: &Recipe,
+        recipes: &[Recipe],
+    ) -> Result<RecipeNutrition, anyhow::Error> {
+        // A nutrient stays unknown once a counted food does not have it.
+        let mut total = Nutrition::default();
+        for value in nutrients_mut(&mut total) {
+            *value = Some(0.0);
+        }
+        let mut unmatched: Vec<(String, String)> = Vec::new();
+        let mut complete = true;
+        for (line, batches) in recipe.ingredient_lines(recipes)? {
+            let text = line.text.localized(None)?;
+            let ingredient = Ingredient::parse(&text);
+            let quantity = match ingredient.quantity {
+                Some(quantity) => quantity,
+                // Lines such as "salt to taste" add too little to count.
+                None => continue,
+            };
+            let measured = ingredient
+                .unit
+                .is_some_and(|unit| VOLUMES.iter().chain(WEIGHTS).any(|(name, _)| *name == unit));
+            let name = ingredient.canonical_name();
+            let food = match self.find(&name) {
+                Some(food) => food,
+                None => {
+                    complete &= !measured;
+                    unmatched.push((text, format!("no food matches {}", name)));
+                    continue;
+                }
+            };
+            let amount = batches
+                * match quantity.max {
+                    Some(max) => (quantity.amount + max) / 2.0,
+                    None => quantity.amount,
+                };
+            match weigh(food, amount, ingredient.unit) {
+                Some(grams) => add(&mut total, &food.nutrition, grams / 100.0),
+                None => {
+                    complete &= !measured;
+                    unmatched.push((
+                        text,
+                        format!(
+                            "{} has no weight per {}",
+                            food.name,
+                            ingredient.unit.unwrap_or("piece")
+                        ),
+                    ))
+                }
+            }
+        }
+
+        let computed: Option<Nutrition> = match recipe.servings {
+            Some(servings) if servings > 0 && complete => {
+                let mut per_serving = total.clone();
+                for value in nutrients_mut(&mut per_serving) {
+                    *value = value.map(|value| value / servings as f64);
+                }
+                Some(per_serving)
+            }
+            _ => None,
+        };
+        let nutrition = match (&recipe.nutrition, computed) {
+            (Some(set), Some(mut computed)) => {
+                for (value, set) in nutrients_mut(&mut computed).into_iter().zip(nutrients(set)) {
+                    if set.is_some() {
+                        *value = set;
+                    }
+                }
+                Some(computed)
+            }
+            (set, computed) => set.clone().or(computed),
+        };
+        Ok(RecipeNutrition {
+            nutrition,
+            unmatched,
+        })
+    }
+}
+
+/// The values of nutrition facts in the order of `NUTRIENT_COLUMNS`.
+pub fn nutrients(nutrition: &Nutrition) -> [Option<f64>; 9] {
+    [
+        nutrition.calories,
+        nutrition.fat,
+        nutrition.saturated_fat,
+        nutrition.cholesterol,
+        nutrition.sodium,
+        nutrition.carbohydrate,
+        nutrition.fiber,
+        nutrition.sugar,
+        nutrition.protein,
+    ]
+}
+
+fn nutrients_mut(nutrition: &mut Nutrition) -> [&mut Option<f64>; 9] {
+    let Nutrition {
+        calories,
+        fat,
+        saturated_fat,
+        cholesterol,
+        sodium,
+        carbohydrate,
+        fiber,
+        sugar,
+        protein,
+    } = nutrition;
+    [
+        calories,
+        fat,
+        saturated_fat,
+        cholesterol,
+        sodium,
+        carbohydrate,
+        fiber,
+        sugar,
+        protein,
+    ]
+}
+
+/// Add nutrition facts multiplied by a factor to a total. A nutrient unknown
+/// in either is unknown in the total.
+fn add(total: &mut Nutrition, nutrition: &Nutrition, factor: f64) {
+    for (value, added) in nutrients_mut(total).into_iter().zip(nutrients(nutrition)) {
+        *value = value
+            .zip(added)
+            .map(|(value, added)| value + added * factor);
+    }
+}
+
+/// The weight in grams of an amount of a food in a unit. Units that are not
+/// volumes or weights, and amounts without a unit, count pieces of the food.
+fn weigh(food: &Food, amount: f64, unit: Option<&str>) -> Option<f64> {
+    if let Some(unit) = unit {
+        if let Some((_, grams)) = WEIGHTS.iter().find(|(name, _)| *name == unit) {
+            return Some(amount * grams);
+        }
+        if let Some((_, milliliters)) = VOLUMES.iter().find(|(name, _)| *name == unit) {
+            let cup = VOLUMES.iter().find(|(name, _)| *name == "cup")?.1;
+            return food
+                .grams_per_cup
+                .map(|grams_per_cup| amount * milliliters / cup * grams_per_cup);
+        }
+    }
+    food.grams_each.map(|grams_each| amount * grams_each)
+}
+
+/// Whether a word of an ingredient is a singular food word or its plural, as
+/// in "tomatoes", "berries" or "leaves".
+fn same_word(word: &str, food_word: &str) -> bool {
+    let word = word.to_lowercase();
+    if word == food_word {
+        return true;
+    }
+    [("s", ""), ("es", ""), ("ies", "y"), ("ves", "f")]
+        .iter()
+        .any(|(suffix, singular)| {
+            word.strip_suffix(suffix)
+                .is_some_and(|stem| format!("{}{}", stem, singular) == food_word)
+        })
+}
+
+/// Split a CSV row into its trimmed cells, allowing quoted cells with commas
+/// and doubled quotes.
+fn split_row(line: &str) -> Vec<String> {
+    let mut cells: Vec<String> = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{IngredientLine, LocalizedString};
+
+    const TABLE: &str = "name,calories,fat,protein,grams_per_cup,grams_each
+sugar,400,0,0,200,
+brown sugar,380,,0.1,220,
+egg,140,10,12.5,,50
+\"flour, all-purpose\",360,1,10,125,
+";
+
+    #[test]
+    fn nutrient_table_ok() {
+        let table = NutrientTable::parse(TABLE).unwrap();
+        assert_eq!(table.foods.len(), 4);
+        assert_eq!(table.foods[1].nutrition.fat, None);
+        assert_eq!(table.foods[1].nutrition.sodium, None);
+        assert_eq!(table.foods[0].nutrition.fat, Some(0.0));
+        assert_eq!(table.foods[2].grams_per_cup, None);
+        assert_eq!(table.foods[3].name, "flour, all-purpose");
+
+        assert_eq!(table.find("light brown sugar").unwrap().name, "brown sugar");
+        assert_eq!(table.find("sugar").unwrap().name, "sugar");
+        assert_eq!(table.find("eggs").unwrap().name, "egg");
+        assert!(table.find("eggplant").is_none());
+
+        assert_eq!(
+            NutrientTable::parse("name,calories\nsugar,lots")
+                .unwrap_err()
+                .to_string(),
+            "line 2: invalid number lots"
+        );
+        assert!(NutrientTable::parse("food,calories\nsugar,1").is_err());
+        assert!(same_word("leaves", "leaf"));
+        assert!(same_word("berries", "berry"));
+        assert!(!same_word("eggplant", "egg"));
+    }
+
+    #[test]
+    fn recipe_nutrition_ok() {
+        let table = NutrientTable::parse(TABLE).unwrap();
+        let mut recipe = Recipe::init(None, None, false);
+        recipe.servings = Some(4);
+        recipe.ingredients = [
+            "1 cup sugar",
+            "2-4 eggs",
+            "salt to taste",
+            "1 pinch saffron",
+            "2 tablespoons of eggs",
+        ]
+        .iter()
+        .map(|text| IngredientLine::new(text))
+        .collect();
+        recipe.nutrition = Some(Nutrition {
+            protein: Some(5.0),
+            ..Nutrition::default()
+        });
+
+        let facts = table.recipe_nutrition(&recipe, &[]).unwrap();
+        assert_eq!(facts.nutrition, recipe.nutrition);
+        assert_eq!(
+            facts.unmatched,
+            vec![
+                (
+                    String::from("1 pinch saffron"),
+                    String::from("no food matches saffron")
+                ),
+                (
+                    String::from("2 tablespoons of eggs"),
+                    String::from("egg has no weight per tablespoon")
+                ),
+            ]
+        );
+
+        recipe.ingredients.truncate(3);
+        let nutrition = table
+            .recipe_nutrition(&recipe, &[])
+            .unwrap()
+            .nutrition
+            .unwrap();
+        // 200 g of sugar and 150 g of eggs for 4 servings.
+        assert_eq!(nutrition.calories, Some((800.0 + 210.0) / 4.0));
+        assert_eq!(nutrition.fat, Some(15.0 / 4.0));
+        assert_eq!(nutrition.protein, Some(5.0));
+        assert_eq!(nutrition.sodium, None);
+
+        // Brown sugar has no fat in the table, so the total fat is unknown.
+        recipe
+            .ingredients
+            .push(IngredientLine::new("1 cup brown sugar"));
+        let nutrition = table
+            .recipe_nutrition(&recipe, &[])
+            .unwrap()
+            .nutrition
+            .unwrap();
+        assert_eq!(nutrition.calories, Some((800.0 + 210.0 + 836.0) / 4.0));
+        assert_eq!(nutrition.fat, None);
+        recipe.ingredients.truncate(3);
+
+        // A garnish that is not measured in a volume or weight is reported
+        // without hiding the rest.
+        recipe
+            .ingredients
+            .push(IngredientLine::new("1 sprig of mint"));
+        let facts = table.recipe_nutrition(&recipe, &[]).unwrap();
+        assert_eq!(
+            facts.unmatched,
+            vec![(
+                String::from("1 sprig of mint"),
+                String::from("no food matches sprig of mint")
+            )]
+        );
+        assert_eq!(
+            facts.nutrition.unwrap().calories,
+            Some((800.0 + 210.0) / 4.0)
+        );
+        recipe.ingredients.truncate(3);
+
+        let mut cake = Recipe::init(None, None, false);
+        cake.ingredients = vec![IngredientLine {
+            text: LocalizedString::new("1 batch of frosting"),
+            recipe: Some(recipe.id),
+        }];
+        let recipes = vec![recipe, cake.clone()];
+        assert_eq!(
+            table.recipe_nutrition(&cake, &recipes).unwrap().nutrition,
+            None
+        );
+
+        cake.servings = Some(2);
+        let facts = table.recipe_nutrition(&cake, &recipes).unwrap();
+        assert!(facts.unmatched.is_empty());
+        assert_eq!(facts.nutrition.unwrap().calories, Some(1010.0 / 2.0));
+    }
+}
@@ -0,0 +1,321 @@
+//! Replace single values in hand-written YAML files such as the recipes,
+//! keeping the comments, key order and quoting of everything else.
+//!
+//! Only the block style used by the recipe files is understood: mappings
+//! and sequences nested by indentation, with flow sequences such as
+//! `locales: ["en_US"]` as leaf values.
+
+use anyhow::anyhow;
+use serde_yaml::Value;
+
+/// One part of a path such as `stages[0].steps[1]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Where a value sits in the lines of a document. The value starts at
+/// `column` of `line` and ends before `end`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    line: usize,
+    column: usize,
+    end: usize,
+    /// The indentation of block content written for the value.
+    indent: usize,
+}
+
+fn parse_path(path: &str) -> Result<Vec<Segment<'_>>, anyhow::Error> {
+    let invalid = || anyhow!("invalid path {}", path);
+    let mut segments = Vec::new();
+
+    for part in path.split('.') {
+        let (key, mut indexes) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if key.is_empty() {
+            return Err(invalid());
+        }
+        segments.push(Segment::Key(key));
+
+        while !indexes.is_empty() {
+            let (index, rest) = indexes
+                .strip_prefix('[')
+                .and_then(|indexes| indexes.split_once(']'))
+                .ok_or_else(invalid)?;
+            segments.push(Segment::Index(index.parse().map_err(|_| invalid())?));
+            indexes = rest;
+        }
+    }
+
+    Ok(segments)
+}
+
+/// The value at a path such as `stages[0].steps[1]`.
+pub fn value_at<'a>(value: &'a Value, path: &str) -> Result<&'a Value, anyhow::Error> {
+    parse_path(path)?
+        .into_iter()
+        .try_fold(value, |value, segment| match segment {
+            Segment::Key(key) => value.get(key),
+            Segment::Index(index) => value.get(index),
+        })
+        .ok_or_else(|| anyhow!("no value at {}", path))
+}
+
+/// Replace the value at `path` in a YAML document with `value`, leaving the
+/// rest of the document untouched.
+pub fn replace(document: &str, path: &str, value: &Value) -> Result<String, anyhow::Error> {
+    let lines: Vec<&str> = document.lines().collect();
+    let segments = parse_path(path)?;
+
+    let root = Span {
+        line: lines
+            .iter()
+            .position(|line| is_content(line) && line.trim() != "---")
+            .unwrap_or(lines.len()),
+        column: 0,
+        end: lines.len(),
+        indent: 0,
+    };
+    let span = segments
+        .iter()
+        .try_fold(root, |span, segment| child(&lines, span, *segment))
+        .ok_or_else(|| anyhow!("no value at {}", path))?;
+
+    let original = lines[span.line][span.column..].trim_start();
+    let compact = matches!(segments.last(), Some(Segment::Index(_)));
+    let rendered = render(value, original, span.indent, compact)?;
+
+    let mut patched: Vec<String> = lines[..span.line]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    patched.push(format!("{}{}", &lines[span.line][..span.column], rendered));
+    patched.extend(lines[span.end..].iter().map(|line| line.to_string()));
+
+    let mut patched = patched.join("\n");
+    if document.ends_with('\n') {
+        patched.push('\n');
+    }
+    Ok(patched)
+}
+
+/// Lines that hold part of a value rather than only whitespace or a comment.
+fn is_content(line: &str) -> bool {
+    let line = line.trim_start();
+    !line.is_empty() && !line.starts_with('#')
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// The line and column where the content of a value starts, which is on
+/// the next line for block content.
+fn content_start(lines: &[&str], span: Span) -> Option<(usize, usize)> {
+    let first = &lines.get(span.line)?[span.column..];
+    if is_content(first) {
+        return Some((span.line, span.column + indentation(first)));
+    }
+    (span.line + 1..span.end)
+        .find(|line| is_content(lines[*line]))
+        .map(|line| (line, indentation(lines[line])))
+}
+
+/// The span of a mapping key or sequence item inside a block value.
+fn child(lines: &[&str], span: Span, segment: Segment) -> Option<Span> {
+    let (line, column) = content_start(lines, span)?;
+
+    // Mapping keys and sequence items start at the column of the content,
+    // a block sequence may share the indentation of the key it belongs to.
+    let is_item = |text: &str| text == "-" || text.starts_with("- ");
+    let entries: Vec<(usize, usize)> = std::iter::once((line, column))
+        .chain(
+            (line + 1..span.end)
+                .filter(|line| is_content(lines[*line]) && indentation(lines[*line]) == column)
+                .map(|line| (line, column)),
+        )
+        .filter(|(line, column)| {
+            let text = &lines[*line][*column..];
+            match segment {
+                Segment::Key(_) => !is_item(text),
+                Segment::Index(_) => is_item(text),
+            }
+        })
+        .collect();
+
+    let position = match segment {
+        Segment::Key(key) => entries.iter().position(|(line, column)| {
+            lines[*line][*column..]
+                .strip_prefix(key)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        })?,
+        Segment::Index(index) if index < entries.len() => index,
+        Segment::Index(_) => return None,
+    };
+    let (line, column) = entries[position];
+
+    let mut end = entries
+        .get(position + 1)
+        .map(|(line, _)| *line)
+        .unwrap_or(span.end);
+    while end > line + 1 && !is_content(lines[end - 1]) {
+        end -= 1;
+    }
+
+    Some(Span {
+        line,
+        column: match segment {
+            Segment::Key(key) => column + key.len() + 1,
+            Segment::Index(_) => column + 1,
+        },
+        end,
+        indent: column + 2,
+    })
+}
+
+/// Write a value the way it would appear after `key:` or `-`. Flow
+/// sequences stay flow sequences and a mapping in a sequence starts on the
+/// line of its `-`.
+fn render(
+    value: &Value,
+    original: &str,
+    indent: usize,
+    compact: bool,
+) -> Result<String, anyhow::Error> {
+    if let (true, Value::Sequence(items)) = (original.starts_with('['), value) {
+        if !items
+            .iter()
+            .any(|item| matches!(item, Value::Sequence(_) | Value::Mapping(_)))
+        {
+            let items = items
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<Result<Vec<String>, _>>()?;
+            return Ok(format!(" [{}]", items.join(", ")));
+        }
+    }
+
+    let yaml = serde_yaml::to_string(value)?;
+    let yaml = yaml.strip_prefix("---\n").unwrap_or(&yaml).trim_end();
+    // Empty sequences and mappings are written in flow style.
+    let block =
+        matches!(value, Value::Sequence(_) | Value::Mapping(_)) && !yaml.starts_with(['[', '{']);
+    if !block {
+        return Ok(format!(" {}", yaml));
+    }
+
+    let padding = " ".repeat(indent);
+    let mut lines = yaml.lines();
+    let mut rendered = match (compact, value) {
+        (true, Value::Mapping(_)) => format!(" {}", lines.next().unwrap_or_default()),
+        _ => String::new(),
+    };
+    for line in lines {
+        rendered.push('\n');
+        rendered.push_str(&padding);
+        rendered.push_str(line);
+    }
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPE: &str = "---
+# A comment that must survive.
+id: 681bf47b-d48e-4c42-942b-303c5ed692df
+locales: [\"en_US\"]
+name: Jambalaya   # the name
+ingredients:
+- 3/4 pound smoked sausage
+- 5 stalks of celery
+stages:
+  - name: Prepare
+    prep_time: 20 minutes
+    steps:
+      - Chop celery into 1/2 inch pieces
+
+      - text: Heat pot to medium heat.
+        duration: 5m
+  - name: Serve
+    steps:
+      - Serve over a bed of rice.
+";
+
+    fn yaml(text: &str) -> Value {
+        serde_yaml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn value_at_ok() {
+        let value = yaml(RECIPE);
+        assert_eq!(
+            value_at(&value, "stages[0].steps[1].duration").unwrap(),
+            &yaml("5m")
+        );
+        assert_eq!(
+            value_at(&value, "ingredients[1]").unwrap(),
+            &yaml("5 stalks of celery")
+        );
+        assert!(value_at(&value, "stages[2]").is_err());
+        assert!(value_at(&value, "stages[x]").is_err());
+    }
+
+    #[test]
+    fn replace_ok() {
+        let patched = replace(RECIPE, "locales", &yaml("[en_US, fr_FR]")).unwrap();
+        let patched = replace(
+            &patched,
+            "name",
+            &yaml("{en_US: Jambalaya, fr_FR: Jambalaya}"),
+        )
+        .unwrap();
+        let patched = replace(&patched, "ingredients[1]", &yaml("5 branches de céleri")).unwrap();
+        let patched = replace(
+            &patched,
+            "stages[0].steps[0]",
+            &yaml("{text: Chop celery into 1/2 inch pieces, equipment: [knife]}"),
+        )
+        .unwrap();
+        let patched = replace(
+            &patched,
+            "stages[0].steps[1]",
+            &yaml("{text: {en_US: Heat pot to medium heat., fr_FR: Chauffer.}, duration: 5m}"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            patched,
+            "---
+# A comment that must survive.
+id: 681bf47b-d48e-4c42-942b-303c5ed692df
+locales: [\"en_US\", \"fr_FR\"]
+name:
+  en_US: Jambalaya
+  fr_FR: Jambalaya
+ingredients:
+- 3/4 pound smoked sausage
+- 5 branches de céleri
+stages:
+  - name: Prepare
+    prep_time: 20 minutes
+    steps:
+      - text: Chop celery into 1/2 inch pieces
+        equipment:
+          - knife
+
+      - text:
+          en_US: Heat pot to medium heat.
+          fr_FR: Chauffer.
+        duration: 5m
+  - name: Serve
+    steps:
+      - Serve over a bed of rice.
+"
+        );
+        assert!(replace(RECIPE, "stages[0].steps[2]", &yaml("x")).is_err());
+        assert!(replace(RECIPE, "description", &yaml("x")).is_err());
+    }
+}
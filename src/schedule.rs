@@ -0,0 +1,217 @@
+use anyhow::anyhow;
+use std::time::Duration;
+
+use crate::model::Recipe;
+
+const MINUTES_IN_DAY: i64 = 24 * 60;
+
+/// When a stage starts and ends, from the start of the recipe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledStage {
+    pub index: usize,
+    pub anchor: String,
+    pub start: Duration,
+    pub end: Duration,
+    pub hands_off: bool,
+    /// Whether delaying the stage delays the whole recipe.
+    pub critical: bool,
+}
+
+/// The stages of a recipe planned for a single cook.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub stages: Vec<ScheduledStage>,
+    pub total: Duration,
+}
+
+impl Schedule {
+    /// Plan the stages of a recipe. A stage starts once the stages it is after
+    /// are done and, unless it is hands-off, once the cook is done with the
+    /// previous hands-on stage. Stages are planned in the order they are
+    /// written and can only be after earlier stages.
+    pub fn new(recipe: &Recipe) -> Result<Self, anyhow::Error> {
+        let anchors: Vec<String> = recipe
+            .stages
+            .iter()
+            .enumerate()
+            .map(|(index, stage)| stage.anchor(index))
+            .collect();
+
+        let mut stages: Vec<ScheduledStage> = Vec::with_capacity(recipe.stages.len());
+        // The stage that the start of each stage waits on.
+        let mut waits_on: Vec<Option<usize>> = Vec::with_capacity(recipe.stages.len());
+        let mut cook_free = Duration::ZERO;
+        let mut cook_last: Option<usize> = None;
+
+        for (index, stage) in recipe.stages.iter().enumerate() {
+            let after: Vec<usize> = match &stage.after {
+                None => index.checked_sub(1).into_iter().collect(),
+                Some(after) => after
+                    .iter()
+                    .map(|id| {
+                        anchors[..index]
+                            .iter()
+                            .position(|anchor| anchor == id)
+                            .ok_or_else(|| {
+                                anyhow!(
+                                    "stage {} is after {}, which is not an earlier stage",
+                                    anchors[index],
+                                    id
+                                )
+                            })
+                    })
+                    .collect::<Result<Vec<usize>, anyhow::Error>>()?,
+            };
+
+            let mut start = Duration::ZERO;
+            let mut waiting_on: Option<usize> = None;
+            for previous in after {
+                if waiting_on.is_none() || stages[previous].end > start {
+                    start = stages[previous].end;
+                    waiting_on = Some(previous);
+                }
+            }
+            if !stage.hands_off && cook_free > start {
+                start = cook_free;
+                waiting_on = cook_last;
+            }
+
+            let end =
+                start + stage.prep_time.unwrap_or_default() + stage.cook_time.unwrap_or_default();
+            if !stage.hands_off {
+                cook_free = end;
+                cook_last = Some(index);
+            }
+
+            stages.push(ScheduledStage {
+                index,
+                anchor: anchors[index].clone(),
+                start,
+                end,
+                hands_off: stage.hands_off,
+                critical: false,
+            });
+            waits_on.push(waiting_on);
+        }
+
+        let total = stages
+            .iter()
+            .map(|stage| stage.end)
+            .max()
+            .unwrap_or_default();
+
+        let mut critical = stages.iter().rposition(|stage| stage.end == total);
+        while let Some(index) = critical {
+            stages[index].critical = true;
+            critical = waits_on[index];
+        }
+
+        Ok(Schedule { stages, total })
+    }
+
+    /// The minute of the day each stage starts at to serve at a minute of the
+    /// day. Minutes before midnight are negative.
+    pub fn start_minutes(&self, serve_at: i64) -> Vec<i64> {
+        let start = serve_at - (self.total.as_secs() as i64 + 59) / 60;
+        self.stages
+            .iter()
+            .map(|stage| start + stage.start.as_secs() as i64 / 60)
+            .collect()
+    }
+}
+
+/// Parse a time of day such as `18:30` into minutes since midnight.
+pub fn parse_clock(src: &str) -> Result<i64, anyhow::Error> {
+    let invalid = || anyhow!("invalid time {}, expected HH:MM", src);
+    let (hours, minutes) = src.trim().split_once(':').ok_or_else(invalid)?;
+    let hours: i64 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+    if !(0..24).contains(&hours) || !(0..60).contains(&minutes) {
+        return Err(invalid());
+    }
+    Ok(hours * 60 + minutes)
+}
+
+/// Write minutes since midnight as `HH:MM`, wrapping times on other days.
+pub fn format_clock(minutes: i64) -> String {
+    let minutes = minutes.rem_euclid(MINUTES_IN_DAY);
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Stage;
+
+    fn stage(name: &str, minutes: u64, after: Option<Vec<&str>>, hands_off: bool) -> Stage {
+        let mut stage = Stage::init(name.to_string());
+        stage.id = Some(name.to_string());
+        stage.prep_time = Some(Duration::from_secs(minutes * 60));
+        stage.after = after.map(|after| after.iter().map(|id| id.to_string()).collect());
+        stage.hands_off = hands_off;
+        stage
+    }
+
+    fn starts(schedule: &Schedule) -> Vec<(u64, bool)> {
+        schedule
+            .stages
+            .iter()
+            .map(|stage| (stage.start.as_secs() / 60, stage.critical))
+            .collect()
+    }
+
+    #[test]
+    fn schedule_ok() {
+        let mut recipe = Recipe::init(None, None, false);
+        recipe.stages = vec![
+            stage("dough", 20, None, false),
+            stage("rise", 60, None, true),
+            stage("filling", 15, Some(vec!["dough"]), false),
+            stage("bake", 25, Some(vec!["rise", "filling"]), false),
+        ];
+        let schedule = Schedule::new(&recipe).unwrap();
+        assert_eq!(schedule.total, Duration::from_secs(105 * 60));
+        assert_eq!(
+            starts(&schedule),
+            vec![(0, true), (20, true), (20, false), (80, true)]
+        );
+
+        // Without dependencies every stage follows the previous one.
+        recipe.stages = vec![
+            stage("prep", 20, None, false),
+            stage("cook", 30, None, false),
+        ];
+        let schedule = Schedule::new(&recipe).unwrap();
+        assert_eq!(schedule.total, Duration::from_secs(50 * 60));
+        assert_eq!(starts(&schedule), vec![(0, true), (20, true)]);
+        assert_eq!(
+            schedule.start_minutes(parse_clock("18:30").unwrap()),
+            vec![1060, 1080]
+        );
+        assert_eq!(format_clock(1060), "17:40");
+        assert_eq!(format_clock(-20), "23:40");
+
+        // Two hands-on stages cannot overlap even without dependencies.
+        recipe.stages = vec![
+            stage("sauce", 10, Some(vec![]), false),
+            stage("salad", 10, Some(vec![]), false),
+        ];
+        let schedule = Schedule::new(&recipe).unwrap();
+        assert_eq!(starts(&schedule), vec![(0, true), (10, true)]);
+    }
+
+    #[test]
+    fn schedule_err() {
+        let mut recipe = Recipe::init(None, None, false);
+        recipe.stages = vec![
+            stage("prep", 20, Some(vec!["cook"]), false),
+            stage("cook", 30, None, false),
+        ];
+        assert_eq!(
+            Schedule::new(&recipe).unwrap_err().to_string(),
+            "stage prep is after cook, which is not an earlier stage"
+        );
+        assert!(parse_clock("25:00").is_err());
+        assert!(parse_clock("6pm").is_err());
+    }
+}
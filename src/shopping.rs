@@ -0,0 +1,754 @@
+use anyhow::anyhow;
+use serde::Serialize;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::catalog::Catalog;
+use crate::ingredient::{best_match, Ingredient};
+use crate::locale::lookup_or_default;
+use crate::model::Recipe;
+
+/// Volume units and their size in milliliters.
+pub const VOLUMES: &[(&str, f64)] = &[
+    ("teaspoon", 4.92892),
+    ("tablespoon", 14.7868),
+    ("fluid ounce", 29.5735),
+    ("cup", 236.588),
+    ("pint", 473.176),
+    ("quart", 946.353),
+    ("gallon", 3785.41),
+    ("milliliter", 1.0),
+    ("liter", 1000.0),
+];
+
+/// Weight units and their size in grams.
+pub const WEIGHTS: &[(&str, f64)] = &[
+    ("ounce", 28.3495),
+    ("pound", 453.592),
+    ("gram", 1.0),
+    ("kilogram", 1000.0),
+];
+
+/// Store sections in the order they are usually walked through, with the
+/// ingredient names they stock. The longest name found in an ingredient
+/// decides its section, so "garlic powder" is a spice and "garlic" produce,
+/// and the last one wins a tie, so "bread flour" is for baking.
+const SECTIONS: &[(&str, &[&str])] = &[
+    (
+        "Produce",
+        &[
+            "apple",
+            "apples",
+            "avocado",
+            "avocados",
+            "basil",
+            "bell pepper",
+            "bell peppers",
+            "carrot",
+            "carrots",
+            "celery",
+            "cilantro",
+            "garlic",
+            "ginger",
+            "lemon",
+            "lemons",
+            "lemon juice",
+            "lettuce",
+            "lime",
+            "limes",
+            "lime wedges",
+            "lime juice",
+            "mushrooms",
+            "onion",
+            "onions",
+            "parsley",
+            "potato",
+            "potatoes",
+            "scallions",
+            "spinach",
+            "tomato",
+            "tomatoes",
+        ],
+    ),
+    (
+        "Meat and Seafood",
+        &[
+            "bacon", "beef", "chicken", "fish", "ham", "pork", "salmon", "sausage", "shrimp",
+            "turkey",
+        ],
+    ),
+    (
+        "Dairy and Eggs",
+        &[
+            "butter",
+            "buttermilk",
+            "cheese",
+            "cream",
+            "egg",
+            "eggs",
+            "egg yolk",
+            "egg yolks",
+            "milk",
+            "orange juice",
+            "sour cream",
+            "yogurt",
+        ],
+    ),
+    ("Bakery", &["bread", "buns", "rolls", "tortillas"]),
+    (
+        "Baking",
+        &[
+            "almonds",
+            "baking powder",
+            "baking soda",
+            "brown sugar",
+            "chocolate",
+            "coconut",
+            "cornstarch",
+            "flour",
+            "honey",
+            "pecans",
+            "pistachios",
+            "shortening",
+            "sugar",
+            "vanilla",
+            "walnuts",
+            "yeast",
+        ],
+    ),
+    (
+        "Spices",
+        &[
+            "bay leaves",
+            "black pepper",
+            "cayenne pepper",
+            "chili powder",
+            "cinnamon",
+            "cumin",
+            "garlic powder",
+            "nutmeg",
+            "onion powder",
+            "oregano",
+            "paprika",
+            "pepper",
+            "salt",
+            "thyme",
+        ],
+    ),
+    (
+        "Pantry",
+        &[
+            "beans",
+            "broth",
+            "chiles",
+            "ketchup",
+            "mayonnaise",
+            "mustard",
+            "oil",
+            "pasta",
+            "pineapple",
+            "rice",
+            "sauce",
+            "stock",
+            "vinegar",
+        ],
+    ),
+    ("Frozen", &["phyllo dough", "puff pastry", "ice cream"]),
+];
+
+/// The section of ingredients that are in no other section.
+const OTHER_SECTION: &str = "Other";
+
+/// The section of canned ingredients, whatever they are.
+const CANNED_SECTION: &str = "Pantry";
+
+/// Ingredients that come out of the tap rather than the store.
+const NOT_BOUGHT: &[&str] = &[
+    "water",
+    "cold water",
+    "hot water",
+    "warm water",
+    "ice water",
+];
+
+/// An amount of an ingredient, such as `1 1/2 cups` or the range `3-6`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Measure {
+    pub amount: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShoppingItem {
+    /// The canonical name of the ingredient.
+    pub name: String,
+    /// The merged amounts of the ingredient, one for each kind of unit. Lines
+    /// without an amount, such as "salt to taste", add none.
+    pub quantities: Vec<Measure>,
+    /// The names of the recipes that use the ingredient.
+    pub recipes: Vec<String>,
+}
+
+impl Measure {
+    /// The amount in the number format of the locale and the unit from the
+    /// `unit.<name>` message of the catalog, such as `2⅓ cups`. Amounts below
+    /// one read as one, as in `½ cup`.
+    pub fn localized(&self, catalog: &Catalog, locale: &str) -> String {
+        let format_locale = lookup_or_default(locale);
+        let amount = match self.max {
+            Some(max) => format!(
+                "{}-{}",
+                format_locale.format_quantity(self.amount),
+                format_locale.format_quantity(max)
+            ),
+            None => format_locale.format_quantity(self.amount),
+        };
+        match &self.unit {
+            Some(unit) => catalog.translate_count(
+                locale,
+                &format!("unit.{}", unit.replace(' ', "_")),
+                Some(self.max.unwrap_or(self.amount).max(1.0)),
+                &[("amount", amount)],
+            ),
+            None => amount,
+        }
+    }
+}
+
+impl ShoppingItem {
+    /// The amounts and name of the item, such as `2⅓ cups sugar`.
+    pub fn label(&self, catalog: &Catalog, locale: &str) -> String {
+        match self.quantities.is_empty() {
+            true => self.name.clone(),
+            false => format!(
+                "{} {}",
+                self.quantities
+                    .iter()
+                    .map(|quantity| quantity.localized(catalog, locale))
+                    .collect::<Vec<String>>()
+                    .join(" + "),
+                self.name
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShoppingSection {
+    pub name: String,
+    pub items: Vec<ShoppingItem>,
+}
+
+impl ShoppingSection {
+    /// The message key of the localized name of the section, such as
+    /// `shopping.meat_and_seafood`.
+    pub fn message_key(&self) -> String {
+        format!("shopping.{}", self.name.to_lowercase().replace(' ', "_"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShoppingRecipe {
+    pub id: Uuid,
+    pub name: String,
+    /// The servings the recipe is scaled to, when known.
+    pub servings: Option<u32>,
+    /// Whether the recipe could not be scaled to the requested servings
+    /// because it does not say how many it makes.
+    pub unscaled: bool,
+}
+
+/// The ingredients of several recipes merged and grouped by store section.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShoppingList {
+    pub recipes: Vec<ShoppingRecipe>,
+    pub sections: Vec<ShoppingSection>,
+}
+
+/// The formats a shopping list can be written in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShoppingListFormat {
+    Markdown,
+    Text,
+    Json,
+}
+
+impl FromStr for ShoppingListFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "markdown" | "md" => Ok(ShoppingListFormat::Markdown),
+            "text" | "txt" => Ok(ShoppingListFormat::Text),
+            "json" => Ok(ShoppingListFormat::Json),
+            _ => Err(anyhow!(
+                "unknown format {}, expected markdown, text or json",
+                src
+            )),
+        }
+    }
+}
+
+/// How a unit measures, to decide which quantities can be added up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dimension {
+    Volume,
+    Weight,
+    /// Units that only add up with themselves, such as cloves or cans, and
+    /// counts without a unit.
+    Unit(Option<&'static str>),
+}
+
+fn dimension(unit: Option<&'static str>) -> (Dimension, f64) {
+    if let Some(unit) = unit {
+        if let Some((_, size)) = VOLUMES.iter().find(|(name, _)| *name == unit) {
+            return (Dimension::Volume, *size);
+        }
+        if let Some((_, size)) = WEIGHTS.iter().find(|(name, _)| *name == unit) {
+            return (Dimension::Weight, *size);
+        }
+    }
+    (Dimension::Unit(unit), 1.0)
+}
+
+/// A quantity of an ingredient line in the base unit of its dimension.
+#[derive(Debug, Clone, Copy)]
+struct Amount {
+    dimension: Dimension,
+    unit: Option<&'static str>,
+    min: f64,
+    max: Option<f64>,
+}
+
+/// An ingredient of the list being merged.
+struct Item {
+    name: String,
+    amounts: Vec<Amount>,
+    /// The names of the recipes that use the ingredient.
+    recipes: Vec<String>,
+    /// Whether the ingredient is bought in a can in any recipe.
+    canned: bool,
+}
+
+impl ShoppingList {
+    /// Merge the ingredients of recipes, scaling each to a number of servings
+    /// when given. The recipes an ingredient or stage is made by following are
+    /// looked up in all the recipes and their ingredients are scaled with the
+    /// recipe that uses them. Ingredients are matched by canonical name, which
+    /// only works for English ingredient lines.
+    pub fn new(
+        recipes: &[(&Recipe, Option<u32>)],
+        all_recipes: &[Recipe],
+        locale: &str,
+    ) -> Result<Self, anyhow::Error> {
+        let mut shopping_recipes: Vec<ShoppingRecipe> = Vec::with_capacity(recipes.len());
+        let mut items: Vec<Item> = Vec::new();
+
+        for (recipe, servings) in recipes {
+            let servings = *servings;
+            let name = recipe.name.localized(Some(locale.to_string()))?;
+            let scale = match (servings, recipe.servings) {
+                (Some(servings), Some(makes)) if makes > 0 => servings as f64 / makes as f64,
+                _ => 1.0,
+            };
+            shopping_recipes.push(ShoppingRecipe {
+                id: recipe.id,
+                name: name.clone(),
+                servings: servings
+                    .filter(|_| recipe.servings.is_some())
+                    .or(recipe.servings),
+                unscaled: servings.is_some() && recipe.servings.is_none(),
+            });
+
+            for (line, batches) in recipe.ingredient_lines(all_recipes)? {
+                let ingredient = Ingredient::parse(&line.text.localized(Some(locale.to_string()))?);
+                let canonical_name = ingredient.canonical_name();
+                if canonical_name.is_empty() || NOT_BOUGHT.contains(&canonical_name.as_str()) {
+                    continue;
+                }
+
+                let index = match items.iter().position(|item| item.name == canonical_name) {
+                    Some(index) => index,
+                    None => {
+                        items.push(Item {
+                            name: canonical_name,
+                            amounts: Vec::new(),
+                            recipes: Vec::new(),
+                            canned: false,
+                        });
+                        items.len() - 1
+                    }
+                };
+                let item = &mut items[index];
+
+                if !item.recipes.contains(&name) {
+                    item.recipes.push(name.clone());
+                }
+                item.canned |= ingredient.unit == Some("can")
+                    || ingredient
+                        .name
+                        .split_whitespace()
+                        .any(|word| word == "can" || word == "cans");
+                if let Some(quantity) = ingredient.quantity {
+                    let (dimension, size) = dimension(ingredient.unit);
+                    item.amounts.push(Amount {
+                        dimension,
+                        unit: ingredient.unit,
+                        min: quantity.amount * size * scale * batches,
+                        max: quantity.max.map(|max| max * size * scale * batches),
+                    });
+                }
+            }
+        }
+
+        let mut sections: Vec<ShoppingSection> = SECTIONS
+            .iter()
+            .map(|(name, _)| *name)
+            .chain([OTHER_SECTION])
+            .map(|name| ShoppingSection {
+                name: name.to_string(),
+                items: Vec::new(),
+            })
+            .collect();
+
+        for item in items {
+            let section = match item.canned {
+                true => CANNED_SECTION,
+                false => store_section(&item.name),
+            };
+            let item = ShoppingItem {
+                quantities: merge(&item.amounts),
+                name: item.name,
+                recipes: item.recipes,
+            };
+            if let Some(section) = sections.iter_mut().find(|s| s.name == section) {
+                section.items.push(item);
+            }
+        }
+        sections.retain(|section| !section.items.is_empty());
+
+        Ok(ShoppingList {
+            recipes: shopping_recipes,
+            sections,
+        })
+    }
+
+    /// Write the list with the headings and units of the locale from the
+    /// catalog.
+    pub fn render(
+        &self,
+        format: ShoppingListFormat,
+        catalog: &Catalog,
+        locale: &str,
+    ) -> Result<String, anyhow::Error> {
+        let title = catalog.translate(locale, "menu.shopping_list", &[]);
+        let mut out = String::new();
+        match format {
+            ShoppingListFormat::Json => {
+                out.push_str(&serde_json::to_string_pretty(self)?);
+                out.push('\n');
+            }
+            ShoppingListFormat::Markdown => {
+                out.push_str(&format!("# {}\n\n", title));
+                for recipe in &self.recipes {
+                    out.push_str(&format!("* {}\n", recipe_label(recipe, catalog, locale)));
+                }
+                for section in &self.sections {
+                    out.push_str(&format!(
+                        "\n## {}\n\n",
+                        catalog.translate(locale, &section.message_key(), &[])
+                    ));
+                    for item in &section.items {
+                        out.push_str(&format!("- [ ] {}\n", item.label(catalog, locale)));
+                    }
+                }
+            }
+            ShoppingListFormat::Text => {
+                out.push_str(&format!("{}\n\n", title));
+                for recipe in &self.recipes {
+                    out.push_str(&format!("{}\n", recipe_label(recipe, catalog, locale)));
+                }
+                for section in &self.sections {
+                    out.push_str(&format!(
+                        "\n{}\n",
+                        catalog.translate(locale, &section.message_key(), &[])
+                    ));
+                    for item in &section.items {
+                        out.push_str(&format!("  {}\n", item.label(catalog, locale)));
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Find the recipe with an id or a slug in any locale. The slug can be given
+/// without the short id it starts with.
+pub fn find_recipe<'a>(recipes: &'a [Recipe], key: &str) -> Result<&'a Recipe, anyhow::Error> {
+    let key = key.trim().trim_matches('/').to_lowercase();
+    recipes
+        .iter()
+        .find(|recipe| {
+            recipe.id.to_string() == key
+                || recipe.id.to_simple().to_string() == key
+                || recipe.slug.inner.values().any(|slug| {
+                    slug == &key
+                        || slug.split_once('-').is_some_and(|(short_id, rest)| {
+                            rest == key && recipe.id.to_string().ends_with(short_id)
+                        })
+                })
+        })
+        .ok_or_else(|| anyhow!("no recipe has the id or slug {}", key))
+}
+
+fn store_section(name: &str) -> &'static str {
+    let stocked = SECTIONS
+        .iter()
+        .flat_map(|(section, names)| names.iter().map(move |stocked| (*section, *stocked)));
+    best_match(name, stocked, |word, stocked| word == stocked).unwrap_or(OTHER_SECTION)
+}
+
+/// Add up the amounts of an ingredient that share a dimension. Volumes and
+/// weights are written in the largest unit used that keeps the amount at
+/// least one.
+fn merge(amounts: &[Amount]) -> Vec<Measure> {
+    let mut dimensions: Vec<Dimension> = Vec::new();
+    for amount in amounts {
+        if !dimensions.contains(&amount.dimension) {
+            dimensions.push(amount.dimension);
+        }
+    }
+
+    dimensions
+        .into_iter()
+        .map(|dimension| {
+            let same: Vec<&Amount> = amounts
+                .iter()
+                .filter(|amount| amount.dimension == dimension)
+                .collect();
+            let min: f64 = same.iter().map(|amount| amount.min).sum();
+            let max: Option<f64> = match same.iter().any(|amount| amount.max.is_some()) {
+                true => Some(
+                    same.iter()
+                        .map(|amount| amount.max.unwrap_or(amount.min))
+                        .sum(),
+                ),
+                false => None,
+            };
+
+            let mut units: Vec<(Option<&'static str>, f64)> = same
+                .iter()
+                .map(|amount| (amount.unit, self::dimension(amount.unit).1))
+                .collect();
+            units.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let (unit, size) = units
+                .iter()
+                .find(|(_, size)| min / size >= 1.0)
+                .or_else(|| units.last())
+                .copied()
+                .unwrap_or((None, 1.0));
+
+            Measure {
+                amount: round(min / size),
+                max: max.map(|max| round(max / size)),
+                unit: unit.map(String::from),
+            }
+        })
+        .collect()
+}
+
+/// Round away the noise of converting between units.
+fn round(amount: f64) -> f64 {
+    (amount * 1000.0).round() / 1000.0
+}
+
+fn recipe_label(recipe: &ShoppingRecipe, catalog: &Catalog, locale: &str) -> String {
+    match (recipe.servings, recipe.unscaled) {
+        (_, true) => catalog.translate(
+            locale,
+            "shopping.not_scaled",
+            &[("name", recipe.name.clone())],
+        ),
+        (Some(servings), false) => format!(
+            "{}, {}",
+            recipe.name,
+            catalog.translate_count(locale, "menu.servings", Some(servings as f64), &[])
+        ),
+        (None, false) => recipe.name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{IngredientLine, LocalizedString};
+    use std::path::Path;
+
+    fn recipe(name: &str, servings: Option<u32>, ingredients: &[&str]) -> Recipe {
+        let mut recipe = Recipe::init(None, None, false);
+        recipe.name = LocalizedString::new(name);
+        recipe.slug = LocalizedString::new(&format!(
+            "{}-{}",
+            &recipe.id.to_simple().to_string()[20..],
+            name.to_lowercase()
+        ));
+        recipe.servings = servings;
+        recipe.ingredients = ingredients
+            .iter()
+            .map(|text| IngredientLine::new(text))
+            .collect();
+        recipe
+    }
+
+    #[test]
+    fn shopping_list_ok() {
+        let recipes = vec![
+            recipe(
+                "Bread",
+                Some(2),
+                &[
+                    "1 cup sugar",
+                    "3 tablespoons of unsalted butter",
+                    "1 3/4 cups water",
+                    "2 cloves garlic",
+                ],
+            ),
+            recipe(
+                "Tacos",
+                None,
+                &[
+                    "1/3 cup granulated sugar",
+                    "1 1/4 cups unsalted butter, melted",
+                    "1/2 pound chicken",
+                    "8 ounces chicken",
+                    "3-6 lime wedges",
+                    "1 garlic",
+                    "1 1-pound 12-ounce can of diced tomatoes",
+                    "salt to taste",
+                    "1/2 teaspoon garlic powder",
+                ],
+            ),
+        ];
+        let selected: Vec<(&Recipe, Option<u32>)> =
+            recipes.iter().map(|recipe| (recipe, Some(4))).collect();
+        let list = ShoppingList::new(&selected, &recipes, "en_US").unwrap();
+        let catalog = Catalog::load(Path::new("templates")).unwrap();
+
+        assert_eq!(list.recipes[0].servings, Some(4));
+        assert!(list.recipes[1].unscaled);
+        let sections: Vec<(&str, Vec<String>)> = list
+            .sections
+            .iter()
+            .map(|section| {
+                (
+                    section.name.as_str(),
+                    section
+                        .items
+                        .iter()
+                        .map(|item| item.label(&catalog, "en_US"))
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                (
+                    "Produce",
+                    vec![
+                        String::from("4 cloves + 1 garlic"),
+                        String::from("3-6 lime wedges")
+                    ]
+                ),
+                ("Meat and Seafood", vec![String::from("1 pound chicken")]),
+                (
+                    "Dairy and Eggs",
+                    vec![String::from("1⅝ cups unsalted butter")]
+                ),
+                ("Baking", vec![String::from("2⅓ cups sugar")]),
+                (
+                    "Spices",
+                    vec![
+                        String::from("salt to taste"),
+                        String::from("½ teaspoon garlic powder")
+                    ]
+                ),
+                ("Pantry", vec![String::from("1 tomatoes")]),
+            ]
+        );
+        assert_eq!(list.sections[2].items[0].recipes, vec!["Bread", "Tacos"]);
+
+        let markdown = list
+            .render(ShoppingListFormat::Markdown, &catalog, "en_US")
+            .unwrap();
+        assert!(markdown.starts_with("# Shopping List\n\n* Bread, 4 servings\n* Tacos (not scaled"));
+        assert!(markdown.contains("\n## Baking\n\n- [ ] 2⅓ cups sugar\n"));
+        let text = list
+            .render(ShoppingListFormat::Text, &catalog, "en_US")
+            .unwrap();
+        assert!(text.contains("\nProduce\n  4 cloves + 1 garlic\n"));
+        let text = list
+            .render(ShoppingListFormat::Text, &catalog, "fr_FR")
+            .unwrap();
+        assert!(text.contains("\nPâtisserie\n  2,33 tasses sugar\n"));
+        assert!(text.contains("\nÉpices\n  salt to taste\n  0,5 cuillère à café garlic powder\n"));
+        let json: serde_json::Value = serde_json::from_str(
+            &list
+                .render(ShoppingListFormat::Json, &catalog, "en_US")
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            json["sections"][3]["items"][0]["quantities"][0]["unit"],
+            "cup"
+        );
+
+        assert_eq!(
+            find_recipe(&recipes, "tacos").unwrap().name,
+            recipes[1].name
+        );
+        assert_eq!(
+            find_recipe(&recipes, &recipes[0].id.to_string())
+                .unwrap()
+                .id,
+            recipes[0].id
+        );
+        assert!(find_recipe(&recipes, "pie").is_err());
+        assert!("pdf".parse::<ShoppingListFormat>().is_err());
+        assert_eq!(store_section("high gluten/bread flour"), "Baking");
+        assert_eq!(store_section("garlic powder"), "Spices");
+        assert_eq!(store_section("paper towels"), OTHER_SECTION);
+    }
+
+    #[test]
+    fn shopping_list_sub_recipes_ok() {
+        let crust = recipe("Crust", None, &["1 cup flour", "1/2 cup butter"]);
+        let mut pie = recipe("Pie", Some(8), &["1 cup sugar", "1/2 cup flour"]);
+        pie.ingredients.insert(
+            0,
+            IngredientLine {
+                text: LocalizedString::new("1 pie crust"),
+                recipe: Some(crust.id),
+            },
+        );
+        let recipes = vec![crust, pie];
+        let list = ShoppingList::new(&[(&recipes[1], Some(16))], &recipes, "en_US").unwrap();
+        let catalog = Catalog::load(Path::new("templates")).unwrap();
+
+        let labels: Vec<String> = list
+            .sections
+            .iter()
+            .flat_map(|section| {
+                section
+                    .items
+                    .iter()
+                    .map(|item| item.label(&catalog, "en_US"))
+            })
+            .collect();
+        assert_eq!(labels, vec!["1 cup butter", "2 cups sugar", "3 cups flour"]);
+        assert_eq!(list.sections[1].items[0].recipes, vec!["Pie"]);
+    }
+}
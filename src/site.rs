@@ -1,25 +1,62 @@
+use anyhow::anyhow;
+use fnv::FnvHasher;
 use handlebars::{Context, Handlebars, Helper, HelperResult, Output, RenderContext};
 use itertools::Itertools;
-use slugify::slugify;
 use std::{
     collections::{HashMap, HashSet},
     fs,
-    path::Path,
+    hash::Hasher,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 use url::Url;
+use uuid::Uuid;
 
+use crate::calendar::{schedule_ics, PUBLISHED_DATE};
+use crate::catalog::Catalog;
+use crate::ingredient::localize_quantity;
+use crate::locale::{hreflang, lookup_or_default};
+use crate::menu::{load_menu_files, Menu};
 use crate::model::{
-    HomeView, LinkListView, OembedJsonView, OembedView, Recipe, RecipePartial, RecipeView,
-    SearchView, SiteMapView, SiteView,
+    check_sub_recipes, HomeView, ImageManifest, LetterIndexView, LetterPartial, LinkListView,
+    LinkPartial, LocalizedString, MenuView, Nutrition, OembedJsonView, OembedView, Recipe,
+    RecipePartial, RecipeView, SearchView, SiteMapLinkPartial, SiteMapView, SiteView,
 };
-use crate::template::{EscapeHelper, FNVHelper, LocaleHelper};
+use crate::nutrition::{nutrients, NutrientTable, NUTRIENT_COLUMNS};
+use crate::schedule::{format_clock, parse_clock};
+use crate::structured_data::RecipeSchema;
+use crate::tag::TagKind;
+use crate::taxonomy::{Taxonomy, TermKind};
+use crate::template::{
+    DirectionHelper, EscapeHelper, FNVHelper, IsolateHelper, LocaleHelper, TranslateHelper,
+};
+
+const MEDIA_DIR: &str = "media";
+const THUMBNAIL_SIZE: i32 = 200;
+const SHARE_CARD_WIDTH: i32 = 1200;
+const SHARE_CARD_HEIGHT: i32 = 630;
+const X_DEFAULT: &str = "x-default";
+const LETTER_INDEX_DIR: &str = "a-z";
+const MENUS_DIR: &str = "menus";
 
+/// The image advertised to social networks and oEmbed consumers for a recipe.
+#[derive(Debug, Clone)]
+struct ShareImage {
+    url: String,
+    width: i32,
+    height: i32,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn build_site(
     recipe_dir: &Path,
+    menu_dir: &Path,
     static_dir: &Path,
     templates_dir: &Path,
     public_dir: &Path,
     site_locales: &[String],
+    taxonomy: &Taxonomy,
+    nutrient_table: Option<&NutrientTable>,
     site: SiteView,
 ) -> Result<(), anyhow::Error> {
     let public_dir_exists: bool = Path::new(public_dir).is_dir();
@@ -33,12 +70,22 @@ pub fn build_site(
     options.content_only = true;
     fs_extra::dir::copy(static_dir, public_dir, &options)?;
 
+    let catalog = Catalog::load(templates_dir)?;
+
     let mut handlebars = Handlebars::new();
 
     handlebars.set_strict_mode(true);
     handlebars.register_helper("escape", Box::new(EscapeHelper));
     handlebars.register_helper("locale-helper", Box::new(LocaleHelper));
     handlebars.register_helper("fnv", Box::new(FNVHelper));
+    handlebars.register_helper("dir", Box::new(DirectionHelper));
+    handlebars.register_helper("isolate", Box::new(IsolateHelper));
+    handlebars.register_helper(
+        "t",
+        Box::new(TranslateHelper {
+            catalog: catalog.clone(),
+        }),
+    );
     handlebars.register_helper(
         "url",
         Box::new(
@@ -68,48 +115,55 @@ pub fn build_site(
         .register_templates_directory(".hbs", templates_dir)
         .expect("cannot load templates");
 
-    let recipe_files: Vec<String> = walkdir::WalkDir::new(recipe_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().display().to_string().ends_with(".yml"))
-        .map(|e| e.path().display().to_string())
-        .collect();
+    let image_manifest = ImageManifest::load(recipe_dir)?;
+    let mut media = MediaStore::new(public_dir)?;
 
-    let mut recipes: Vec<Recipe> = Vec::with_capacity(recipe_files.len());
-    let mut recipe_ids: HashSet<String> = HashSet::new();
+    let recipes = load_recipes(recipe_dir)?;
+    check_sub_recipes(&recipes)?;
+    let menus: Vec<Menu> = match site.features.menus {
+        true => load_menu_files(menu_dir)?
+            .into_iter()
+            .map(|(menu_file, menu)| {
+                menu.validate(&recipes, taxonomy)
+                    .map_err(|err| anyhow!("{}: {}", menu_file.display(), err))?;
+                Ok(menu)
+            })
+            .collect::<Result<Vec<Menu>, anyhow::Error>>()?,
+        false => Vec::new(),
+    };
+    let alternates = collect_alternates(
+        &recipes,
+        &menus,
+        site_locales,
+        taxonomy,
+        &site.public_url,
+        &site.default_locale,
+    )?;
 
     let mut site_links: HashSet<String> = HashSet::new();
     site_links.insert(site.public_url.clone());
 
-    for recipe_file in &recipe_files {
-        let recipe_yaml = fs::read_to_string(&recipe_file)?;
-        let deserialized_recipe: Recipe = serde_yaml::from_str(&recipe_yaml)?;
-
-        if !recipe_ids.insert(deserialized_recipe.id.to_string()) {
-            error!("duplicate recipe id: {}", deserialized_recipe.id);
-            continue;
-        }
-
-        recipes.push(deserialized_recipe);
-    }
-
-    recipes.sort_by(|a, b| {
-        a.name
-            .localized(None)
-            .unwrap()
-            .cmp(&b.name.localized(None).unwrap())
-    });
+    let calendar_serve_at = parse_clock(&site.calendar.serve_at)?;
 
     for site_locale in site_locales {
-        let locale_root = Path::new(public_dir).join(&site_locale);
+        let locale_root = Path::new(public_dir).join(site_locale);
+        fs::create_dir_all(&locale_root).expect("cannot create locale directory");
 
-        let mut search_views: Vec<SearchView> = Vec::with_capacity(recipe_files.len());
+        let mut search_views: Vec<SearchView> = Vec::with_capacity(recipes.len());
 
         site_links.insert(format!("{}{}/", site.public_url, site_locale));
 
-        let mut categorized_recipes: HashMap<String, Vec<(String, String)>> = HashMap::new();
-        let mut cuisine_recipes: HashMap<String, Vec<(String, String)>> = HashMap::new();
-        let mut recipe_links: Vec<(String, String)> = vec![];
+        let mut categorized_recipes: HashMap<String, Vec<LinkPartial>> = HashMap::new();
+        let mut cuisine_recipes: HashMap<String, Vec<LinkPartial>> = HashMap::new();
+        let mut tagged_recipes: HashMap<TagKind, HashMap<String, (String, Vec<LinkPartial>)>> =
+            HashMap::new();
+        let locale_url = Url::parse(&site.public_url)?.join(&format!("{}/", site_locale))?;
+        let tag_pages_url: Option<&Url> = match site.features.tag_pages {
+            true => Some(&locale_url),
+            false => None,
+        };
+        let mut recipe_links: Vec<LinkPartial> = vec![];
+        let mut recipe_links_by_id: HashMap<Uuid, LinkPartial> = HashMap::new();
 
         for recipe in &recipes {
             debug!("{}", recipe);
@@ -119,13 +173,18 @@ pub fn build_site(
                 Path::new(&recipe_dir).join(format!("{}_thumbnail.jpg", recipe.id));
 
             let mut images: Vec<(String, String)> = Vec::new();
-            let has_images = image_path.exists() && thumbnail_path.exists();
-            if has_images {
-                images.push((
-                    format!("{}_thumbnail.jpg", recipe.id),
-                    format!("{}.jpg", recipe.id),
-                ));
+            if image_path.exists() && thumbnail_path.exists() {
+                images.push((media.publish(&thumbnail_path)?, media.publish(&image_path)?));
             }
+            let thumbnail_url: Option<String> = images
+                .first()
+                .map(|(thumbnail, _)| format!("{}{}", site.public_url, thumbnail));
+            let nutrition: Option<Nutrition> = match nutrient_table {
+                Some(nutrient_table) => {
+                    nutrient_table.recipe_nutrition(recipe, &recipes)?.nutrition
+                }
+                None => recipe.nutrition.clone(),
+            };
 
             for locale in &recipe.locales {
                 if locale != site_locale {
@@ -139,16 +198,25 @@ pub fn build_site(
                     panic!("unable to create recipe root {}", recipe_root.display())
                 });
 
-                if has_images {
-                    fs::copy(&image_path, recipe_root.join(format!("{}.jpg", recipe.id)))?;
-                    fs::copy(
-                        &thumbnail_path,
-                        recipe_root.join(format!("{}_thumbnail.jpg", recipe.id)),
-                    )?;
-                }
+                let share_card_path = Path::new(&recipe_dir).join(recipe.share_card_file(locale));
+                let share_card: Option<String> = match share_card_path.exists() {
+                    true => Some(media.publish(&share_card_path)?),
+                    false => None,
+                };
 
-                let localized_recipe =
-                    recipe.to_partial(Some(locale.clone()), site_locales, images.clone())?;
+                let mut localized_recipe = recipe.to_partial(
+                    Some(locale.clone()),
+                    site_locales,
+                    images.clone(),
+                    image_manifest.images.get(&recipe.id),
+                    taxonomy,
+                )?;
+                for (stage, partial) in recipe.stages.iter().zip(&mut localized_recipe.stages) {
+                    partial.recipe = match stage.recipe {
+                        Some(id) => sub_recipe_link(&recipes, id, &locale_url, locale)?,
+                        None => None,
+                    };
+                }
 
                 let self_url = Url::parse(&site.public_url)?
                     .join(&format!("{}/", site_locale))?
@@ -156,67 +224,110 @@ pub fn build_site(
 
                 site_links.insert(self_url.to_string());
 
-                recipe_links.push((self_url.to_string(), localized_recipe.name.clone()));
+                let recipe_link = LinkPartial::recipe(
+                    self_url.as_str(),
+                    &localized_recipe,
+                    thumbnail_url.clone(),
+                );
 
-                if let Some(x) = categorized_recipes.get_mut(&localized_recipe.category) {
-                    x.push((self_url.to_string(), String::from(&localized_recipe.name)));
-                } else {
-                    categorized_recipes.insert(
-                        localized_recipe.category.clone(),
-                        vec![(self_url.to_string(), String::from(&localized_recipe.name))],
-                    );
+                recipe_links.push(recipe_link.clone());
+                recipe_links_by_id.insert(recipe.id, recipe_link.clone());
+
+                for term in taxonomy.lineage(TermKind::Category, &recipe.category) {
+                    categorized_recipes
+                        .entry(term.id.clone())
+                        .or_default()
+                        .push(recipe_link.clone());
                 }
 
-                if let Some(x) = cuisine_recipes.get_mut(&localized_recipe.cuisine) {
-                    x.push((self_url.to_string(), String::from(&localized_recipe.name)));
-                } else {
-                    cuisine_recipes.insert(
-                        localized_recipe.cuisine.clone(),
-                        vec![(self_url.to_string(), String::from(&localized_recipe.name))],
-                    );
+                for term in taxonomy.lineage(TermKind::Cuisine, &recipe.cuisine) {
+                    cuisine_recipes
+                        .entry(term.id.clone())
+                        .or_default()
+                        .push(recipe_link.clone());
                 }
 
+                for kind in TagKind::ALL {
+                    let tags = tagged_recipes.entry(kind).or_default();
+                    for (key, label) in kind.tags(recipe, locale)? {
+                        tags.entry(key)
+                            .or_insert_with(|| (label, Vec::new()))
+                            .1
+                            .push(recipe_link.clone());
+                    }
+                }
+
+                let share_image: Option<ShareImage> = match (&share_card, &thumbnail_url) {
+                    (Some(share_card), _) => Some(ShareImage {
+                        url: format!("{}{}", site.public_url, share_card),
+                        width: SHARE_CARD_WIDTH,
+                        height: SHARE_CARD_HEIGHT,
+                    }),
+                    (None, Some(thumbnail_url)) => Some(ShareImage {
+                        url: thumbnail_url.clone(),
+                        width: THUMBNAIL_SIZE,
+                        height: THUMBNAIL_SIZE,
+                    }),
+                    (None, None) => None,
+                };
+
+                let calendar_url: Option<Url> =
+                    match site.features.calendar && !localized_recipe.schedule.is_empty() {
+                        true => Some(self_url.join("schedule.ics")?),
+                        false => None,
+                    };
+
                 let mut recipe_meta = vec![
-                    (String::from("twitter:card"), String::from("summary")),
                     (
-                        String::from("twitter:site"),
-                        String::from("@justrecipesblog"),
+                        String::from("twitter:card"),
+                        match share_card.is_some() {
+                            true => String::from("summary_large_image"),
+                            false => String::from("summary"),
+                        },
                     ),
                     (String::from("og:url"), self_url.to_string()),
                     (String::from("twitter:url"), self_url.to_string()),
                     (String::from("og:title"), localized_recipe.name.clone()),
                     (String::from("og:locale"), site_locale.to_string()),
+                    (String::from("og:site_name"), site.name.clone()),
                     (
-                        String::from("og:site_name"),
-                        String::from("Just Recipes Blog"),
+                        String::from("twitter:label1"),
+                        catalog.translate(site_locale, "meta.cuisine", &[]),
                     ),
-                    (String::from("twitter:label1"), String::from("Cuisine")),
                     (
                         String::from("twitter:data1"),
                         localized_recipe.cuisine.clone(),
                     ),
-                    (String::from("twitter:label2"), String::from("Category")),
+                    (
+                        String::from("twitter:label2"),
+                        catalog.translate(site_locale, "meta.category", &[]),
+                    ),
                     (
                         String::from("twitter:data2"),
                         localized_recipe.category.clone(),
                     ),
                 ];
+                if let Some(twitter) = &site.social.twitter {
+                    recipe_meta.push((String::from("twitter:site"), twitter.clone()));
+                }
                 if localized_recipe.description.is_some() {
                     recipe_meta.push((
                         String::from("og:description"),
                         localized_recipe.description.clone().unwrap().clone(),
                     ))
                 }
-                if has_images {
+                if let Some(share_image) = &share_image {
+                    recipe_meta.push((String::from("og:image"), share_image.url.clone()));
+                    recipe_meta.push((String::from("twitter:image"), share_image.url.clone()));
+                    recipe_meta.push((String::from("og:image:type"), String::from("image/jpeg")));
                     recipe_meta.push((
-                        String::from("og:image"),
-                        self_url
-                            .join(&format!("{}_thumbnail.jpg", recipe.id))?
-                            .to_string(),
+                        String::from("og:image:width"),
+                        share_image.width.to_string(),
+                    ));
+                    recipe_meta.push((
+                        String::from("og:image:height"),
+                        share_image.height.to_string(),
                     ));
-                    recipe_meta.push((String::from("og:image:type"), String::from("image/jpeg")));
-                    recipe_meta.push((String::from("og:image:width"), String::from("200")));
-                    recipe_meta.push((String::from("og:image:height "), String::from("200")));
                 }
 
                 let recipe_html = handlebars
@@ -224,13 +335,54 @@ pub fn build_site(
                         "recipe",
                         &RecipeView {
                             locale: site_locale.clone(),
-                            title: format!("Just Recipes - {}", localized_recipe.name).to_string(),
+                            title: catalog.translate(
+                                site_locale,
+                                "page.recipe.title",
+                                &[
+                                    ("site", site.name.clone()),
+                                    ("name", localized_recipe.name.clone()),
+                                ],
+                            ),
                             recipe: localized_recipe.clone(),
                             site: site.clone(),
-                            flat_steps: localized_recipe.flat_steps(),
+                            structured_data: RecipeSchema::new(
+                                recipe,
+                                &localized_recipe,
+                                locale,
+                                &self_url,
+                                images
+                                    .iter()
+                                    .map(|(_, image)| format!("{}{}", site.public_url, image))
+                                    .collect(),
+                                &site.authors,
+                                nutrition.as_ref(),
+                            )?
+                            .to_json_ld()?,
                             self_url: self_url.to_string(),
                             meta: recipe_meta,
-                            oembed_url: self_url.join("oembed.json")?.to_string(),
+                            oembed_url: match site.features.oembed {
+                                true => Some(self_url.join("oembed.json")?.to_string()),
+                                false => None,
+                            },
+                            calendar_url: calendar_url.as_ref().map(|url| url.to_string()),
+                            calendar_serve: calendar_url.as_ref().map(|_| {
+                                format!("{}T{}", PUBLISHED_DATE, format_clock(calendar_serve_at))
+                            }),
+                            alternates: alternates.links(self_url.as_str()),
+                            ingredients: ingredient_links(recipe, &recipes, &locale_url, locale)?,
+                            keywords: tag_links(
+                                TagKind::Keyword,
+                                recipe.keywords.as_deref().unwrap_or_default(),
+                                tag_pages_url,
+                                locale,
+                            )?,
+                            equipment: tag_links(
+                                TagKind::Equipment,
+                                recipe.equipment.as_deref().unwrap_or_default(),
+                                tag_pages_url,
+                                locale,
+                            )?,
+                            nutrition: nutrition_facts(nutrition.as_ref(), &catalog, locale),
                         },
                     )
                     .unwrap();
@@ -244,6 +396,19 @@ pub fn build_site(
                     )
                 });
 
+                if calendar_url.is_some() {
+                    let recipe_ics = schedule_ics(
+                        recipe,
+                        &localized_recipe.name,
+                        &localized_recipe.stages,
+                        PUBLISHED_DATE,
+                        calendar_serve_at,
+                        Some(self_url.as_str()),
+                        UNIX_EPOCH,
+                    )?;
+                    fs::write(recipe_root.join("schedule.ics"), recipe_ics)?;
+                }
+
                 let recipe_json = serde_json::to_string(&recipe)?;
                 let destination_json = recipe_root.join("index.json");
                 fs::write(&destination_json, recipe_json).unwrap_or_else(|_| {
@@ -264,16 +429,151 @@ pub fn build_site(
                     ),
                 });
 
-                write_oembed(
-                    &handlebars,
-                    &recipe_root,
-                    self_url,
-                    &localized_recipe,
-                    site.clone(),
+                if site.features.oembed {
+                    write_oembed(
+                        &handlebars,
+                        &recipe_root,
+                        self_url,
+                        &localized_recipe,
+                        site.clone(),
+                        site_locale,
+                        thumbnail_url.clone(),
+                        share_image,
+                    )?;
+                }
+            }
+        }
+
+        let collation = lookup_or_default(site_locale);
+        recipe_links.sort_by(|a, b| collation.compare(&a.label, &b.label));
+
+        if site.features.menus {
+            let menus_url = locale_url.join(&format!("{}/", MENUS_DIR))?;
+            let mut menu_links: Vec<LinkPartial> = Vec::new();
+
+            for menu in menus
+                .iter()
+                .filter(|menu| menu.locales.contains(site_locale))
+            {
+                let localized_menu = menu.to_partial(
                     site_locale,
-                    has_images,
+                    &recipes,
+                    &recipe_links_by_id,
+                    &site.calendar.serve_at,
+                    &catalog,
                 )?;
+                let self_url = menus_url.join(&format!("{}/", localized_menu.slug))?;
+
+                // Menus are pictured by their first recipe.
+                let mut menu_link = LinkPartial::new(self_url.as_str(), &localized_menu.name);
+                if let Some(first) = menu
+                    .entries
+                    .first()
+                    .and_then(|entry| recipe_links_by_id.get(&entry.recipe))
+                {
+                    menu_link.thumbnail_url = first.thumbnail_url.clone();
+                    menu_link.image_color = first.image_color.clone();
+                    menu_link.image_placeholder = first.image_placeholder.clone();
+                }
+
+                if let Some(category) = &menu.category {
+                    for term in taxonomy.lineage(TermKind::Category, category) {
+                        categorized_recipes
+                            .entry(term.id.clone())
+                            .or_default()
+                            .push(menu_link.clone());
+                    }
+                }
+
+                let menu_html = handlebars.render(
+                    "menu",
+                    &MenuView {
+                        locale: site_locale.clone(),
+                        title: catalog.translate(
+                            site_locale,
+                            "page.menu.title",
+                            &[
+                                ("site", site.name.clone()),
+                                ("name", localized_menu.name.clone()),
+                            ],
+                        ),
+                        site: site.clone(),
+                        self_url: self_url.to_string(),
+                        alternates: alternates.links(self_url.as_str()),
+                        category: match &menu.category {
+                            Some(category) => Some((
+                                taxonomy.label(
+                                    TermKind::Category,
+                                    category,
+                                    Some(site_locale.clone()),
+                                ),
+                                locale_url
+                                    .join(&format!("categories/{}/", category))?
+                                    .to_string(),
+                            )),
+                            None => None,
+                        },
+                        equipment: tag_links(
+                            TagKind::Equipment,
+                            &menu.equipment(site_locale, &recipes)?,
+                            tag_pages_url,
+                            site_locale,
+                        )?,
+                        menu: localized_menu.clone(),
+                    },
+                )?;
+
+                let menu_root = locale_root.join(MENUS_DIR).join(&localized_menu.slug);
+                fs::create_dir_all(&menu_root)?;
+                fs::write(menu_root.join("index.html"), menu_html)?;
+                site_links.insert(self_url.to_string());
+
+                search_views.push(SearchView {
+                    name: localized_menu.name.clone(),
+                    link: self_url.to_string(),
+                });
+                menu_links.push(menu_link);
             }
+            menu_links.sort_by(|a, b| collation.compare(&a.label, &b.label));
+
+            let menus_label = catalog.translate(site_locale, "menus.label", &[]);
+            let menus_html = handlebars.render(
+                "link_list",
+                &LinkListView {
+                    locale: site_locale.clone(),
+                    title: catalog.translate(
+                        site_locale,
+                        "page.group.title",
+                        &[("site", site.name.clone()), ("group", menus_label.clone())],
+                    ),
+                    links_label: menus_label,
+                    preface: None,
+                    links: menu_links,
+                    site: site.clone(),
+                    self_url: menus_url.to_string(),
+                    alternates: alternates.links(menus_url.as_str()),
+                },
+            )?;
+            let menus_root = locale_root.join(MENUS_DIR);
+            fs::create_dir_all(&menus_root)?;
+            fs::write(menus_root.join("index.html"), menus_html)?;
+            site_links.insert(menus_url.to_string());
+        }
+
+        if site.features.letter_index {
+            write_letter_index(
+                &handlebars,
+                &Path::new(&locale_root).join(LETTER_INDEX_DIR),
+                Url::parse(&site.public_url)?
+                    .join(&format!("{}/", site_locale))?
+                    .join(&format!("{}/", LETTER_INDEX_DIR))?,
+                &catalog,
+                &alternates,
+                site.clone(),
+                site_locale,
+                &recipe_links,
+                &mut site_links,
+            )?;
         }
 
         let index_html = handlebars
@@ -281,11 +581,17 @@ pub fn build_site(
                 "link_list",
                 &LinkListView {
                     locale: site_locale.clone(),
-                    title: "Just Recipes - Home".to_string(),
-                    links_label: "All Recipes".to_string(),
+                    title: catalog.translate(
+                        site_locale,
+                        "page.home.title",
+                        &[("site", site.name.clone())],
+                    ),
+                    links_label: catalog.translate(site_locale, "recipes.all", &[]),
+                    preface: None,
                     links: recipe_links,
                     site: site.clone(),
                     self_url: format!("{}{}/", &site.public_url, &site_locale),
+                    alternates: alternates.links(&format!("{}{}/", &site.public_url, &site_locale)),
                 },
             )
             .expect("unable to render index");
@@ -293,15 +599,17 @@ pub fn build_site(
         fs::write(&index_destination, index_html)
             .unwrap_or_else(|_| panic!("unable to write index to {}", index_destination.display()));
 
-        let search_json = serde_json::to_string(&search_views)?;
+        if site.features.search {
+            let search_json = serde_json::to_string(&search_views)?;
 
-        let search_destination = Path::new(&locale_root).join("search.json");
-        fs::write(&search_destination, search_json).unwrap_or_else(|_| {
-            panic!(
-                "unable to write search json to {}",
-                search_destination.display()
-            )
-        });
+            let search_destination = Path::new(&locale_root).join("search.json");
+            fs::write(&search_destination, search_json).unwrap_or_else(|_| {
+                panic!(
+                    "unable to write search json to {}",
+                    search_destination.display()
+                )
+            });
+        }
 
         write_indexes(
             &handlebars,
@@ -309,7 +617,10 @@ pub fn build_site(
             Url::parse(&site.public_url)?
                 .join(&format!("{}/", site_locale))?
                 .join("categories/")?,
-            String::from("categories"),
+            TermKind::Category,
+            taxonomy,
+            &catalog,
+            &alternates,
             site.clone(),
             site_locale,
             categorized_recipes,
@@ -322,12 +633,32 @@ pub fn build_site(
             Url::parse(&site.public_url)?
                 .join(&format!("{}/", site_locale))?
                 .join("cuisines/")?,
-            String::from("cuisines"),
+            TermKind::Cuisine,
+            taxonomy,
+            &catalog,
+            &alternates,
             site.clone(),
             site_locale,
             cuisine_recipes,
             &mut site_links,
         )?;
+
+        if site.features.tag_pages {
+            for kind in TagKind::ALL {
+                write_tag_indexes(
+                    &handlebars,
+                    &Path::new(&locale_root).join(kind.group_type()),
+                    locale_url.join(&format!("{}/", kind.group_type()))?,
+                    kind,
+                    &catalog,
+                    &alternates,
+                    site.clone(),
+                    site_locale,
+                    tagged_recipes.remove(&kind).unwrap_or_default(),
+                    &mut site_links,
+                )?;
+            }
+        }
     }
 
     let home_html = handlebars
@@ -335,9 +666,14 @@ pub fn build_site(
             "index",
             &HomeView {
                 locales: site_locales.to_vec(),
-                title: "Just Recipes - Home".to_string(),
+                title: catalog.translate(
+                    &site.default_locale,
+                    "page.home.title",
+                    &[("site", site.name.clone())],
+                ),
                 site: site.clone(),
                 self_url: site.public_url.clone(),
+                alternates: alternates.links(&site.public_url),
             },
         )
         .expect("unable to render index");
@@ -350,9 +686,14 @@ pub fn build_site(
             "about",
             &HomeView {
                 locales: site_locales.to_vec(),
-                title: "Just Recipes Blog - About".to_string(),
+                title: catalog.translate(
+                    &site.default_locale,
+                    "page.about.title",
+                    &[("site", site.name.clone())],
+                ),
                 site: site.clone(),
                 self_url: format!("{}about/", &site.public_url),
+                alternates: Vec::new(),
             },
         )
         .expect("unable to render about");
@@ -366,7 +707,14 @@ pub fn build_site(
         .render(
             "sitemap",
             &SiteMapView {
-                links: site_links.into_iter().collect(),
+                links: site_links
+                    .into_iter()
+                    .sorted()
+                    .map(|url| SiteMapLinkPartial {
+                        alternates: alternates.links(&url),
+                        url,
+                    })
+                    .collect(),
                 site: site.clone(),
             },
         )
@@ -380,47 +728,354 @@ pub fn build_site(
         )
     });
 
+    let robots_txt = handlebars.render("robots", &site)?;
+    fs::write(Path::new(&public_dir).join("robots.txt"), robots_txt)?;
+
+    if !site.authors.is_empty() {
+        let humans_txt = handlebars.render("humans", &site)?;
+        fs::write(Path::new(&public_dir).join("humans.txt"), humans_txt)?;
+    }
+
+    let webmanifest = serde_json::json!({
+        "name": site.name,
+        "short_name": site.name,
+        "icons": [
+            {"src": "/android-chrome-192x192.png", "sizes": "192x192", "type": "image/png"},
+            {"src": "/android-chrome-512x512.png", "sizes": "512x512", "type": "image/png"},
+        ],
+        "theme_color": "#ffffff",
+        "background_color": "#ffffff",
+        "display": "standalone",
+    });
+    fs::write(
+        Path::new(&public_dir).join("site.webmanifest"),
+        serde_json::to_string(&webmanifest)?,
+    )?;
+
     Ok(())
 }
 
+/// The language versions of every page, grouped by a locale independent page
+/// key, used for `hreflang` links in page heads and the sitemap.
+#[derive(Debug, Default)]
+struct Alternates {
+    pages: HashMap<String, Vec<(String, String)>>,
+    page_keys: HashMap<String, String>,
+    /// The hreflang of the version used as `x-default` when a page has none.
+    default_hreflang: String,
+}
+
+impl Alternates {
+    fn add(&mut self, page_key: &str, hreflang: String, url: String) {
+        let versions = self.pages.entry(page_key.to_string()).or_default();
+        if versions.iter().any(|(existing, _)| existing == &hreflang) {
+            return;
+        }
+        self.page_keys.insert(url.clone(), page_key.to_string());
+        versions.push((hreflang, url));
+    }
+
+    /// The `(hreflang, url)` alternates of the page at the url, including an
+    /// `x-default` version. Pages available in a single locale have none.
+    fn links(&self, url: &str) -> Vec<(String, String)> {
+        let versions = match self
+            .page_keys
+            .get(url)
+            .and_then(|page_key| self.pages.get(page_key))
+        {
+            Some(versions) => versions,
+            None => return Vec::new(),
+        };
+
+        let localized = versions
+            .iter()
+            .filter(|(hreflang, _)| hreflang != X_DEFAULT)
+            .count();
+        if localized < 2 {
+            return Vec::new();
+        }
+
+        let mut links = versions.clone();
+        if !links.iter().any(|(hreflang, _)| hreflang == X_DEFAULT) {
+            let default_url = links
+                .iter()
+                .find(|(found, _)| found == &self.default_hreflang)
+                .or_else(|| links.first())
+                .map(|(_, url)| url.clone())
+                .unwrap_or_default();
+            links.push((X_DEFAULT.to_string(), default_url));
+        }
+        links
+    }
+}
+
+/// Find the url of every page in every site locale. Recipes are matched
+/// across locales by id, taxonomy pages by term id and tag pages by key.
+fn collect_alternates(
+    recipes: &[Recipe],
+    menus: &[Menu],
+    site_locales: &[String],
+    taxonomy: &Taxonomy,
+    public_url: &str,
+    default_locale: &str,
+) -> Result<Alternates, anyhow::Error> {
+    let mut alternates = Alternates {
+        default_hreflang: hreflang(default_locale),
+        ..Alternates::default()
+    };
+    alternates.add("index", X_DEFAULT.to_string(), public_url.to_string());
+
+    for site_locale in site_locales {
+        let language = hreflang(site_locale);
+        let locale_url = Url::parse(public_url)?.join(&format!("{}/", site_locale))?;
+        alternates.add("index", language.clone(), locale_url.to_string());
+
+        alternates.add(
+            LETTER_INDEX_DIR,
+            language.clone(),
+            locale_url
+                .join(&format!("{}/", LETTER_INDEX_DIR))?
+                .to_string(),
+        );
+
+        let menus_url = locale_url.join(&format!("{}/", MENUS_DIR))?;
+        alternates.add(MENUS_DIR, language.clone(), menus_url.to_string());
+        for menu in menus {
+            if !menu.locales.contains(site_locale) {
+                continue;
+            }
+            let slug = menu.slug.localized(Some(site_locale.clone()))?;
+            alternates.add(
+                &format!("menu:{}", menu.id),
+                language.clone(),
+                menus_url.join(&format!("{}/", slug))?.to_string(),
+            );
+            if let Some(category) = &menu.category {
+                for term in taxonomy.lineage(TermKind::Category, category) {
+                    alternates.add(
+                        &format!("{}:{}", TermKind::Category.group_type(), term.id),
+                        language.clone(),
+                        locale_url
+                            .join(&format!("{}/{}/", TermKind::Category.group_type(), term.id))?
+                            .to_string(),
+                    );
+                }
+            }
+        }
+
+        for kind in [TermKind::Category, TermKind::Cuisine] {
+            let group_type = kind.group_type();
+            let group_url = locale_url.join(&format!("{}/", group_type))?;
+            alternates.add(group_type, language.clone(), group_url.to_string());
+
+            for recipe in recipes {
+                if !recipe.locales.contains(site_locale) {
+                    continue;
+                }
+                let group = match kind {
+                    TermKind::Category => &recipe.category,
+                    TermKind::Cuisine => &recipe.cuisine,
+                };
+                for term in taxonomy.lineage(kind, group) {
+                    alternates.add(
+                        &format!("{}:{}", group_type, term.id),
+                        language.clone(),
+                        group_url.join(&format!("{}/", term.id))?.to_string(),
+                    );
+                }
+            }
+        }
+
+        for kind in TagKind::ALL {
+            let group_type = kind.group_type();
+            let group_url = locale_url.join(&format!("{}/", group_type))?;
+            alternates.add(group_type, language.clone(), group_url.to_string());
+
+            for recipe in recipes {
+                if !recipe.locales.contains(site_locale) {
+                    continue;
+                }
+                for (key, _) in kind.tags(recipe, site_locale)? {
+                    alternates.add(
+                        &format!("{}:{}", group_type, key),
+                        language.clone(),
+                        group_url.join(&format!("{}/", key))?.to_string(),
+                    );
+                }
+            }
+        }
+
+        for recipe in recipes {
+            if !recipe.locales.contains(site_locale) {
+                continue;
+            }
+            let slug = recipe.slug.localized(Some(site_locale.clone()))?;
+            alternates.add(
+                &format!("recipe:{}", recipe.id),
+                language.clone(),
+                locale_url.join(&format!("{}/", slug))?.to_string(),
+            );
+        }
+    }
+
+    Ok(alternates)
+}
+
+/// Publishes files into a single media directory shared by all locales. Files
+/// are named by a hash of their content so they can be cached indefinitely.
+#[derive(Debug)]
+struct MediaStore {
+    media_dir: PathBuf,
+    published: HashMap<PathBuf, String>,
+}
+
+impl MediaStore {
+    fn new(public_dir: &Path) -> Result<Self, anyhow::Error> {
+        let media_dir = public_dir.join(MEDIA_DIR);
+        fs::create_dir_all(&media_dir)?;
+        Ok(MediaStore {
+            media_dir,
+            published: HashMap::new(),
+        })
+    }
+
+    /// Copy the file into the media directory, if it hasn't been already, and
+    /// return its path relative to the site root.
+    fn publish(&mut self, source: &Path) -> Result<String, anyhow::Error> {
+        if let Some(published) = self.published.get(source) {
+            return Ok(published.clone());
+        }
+
+        let content = fs::read(source)?;
+        let mut fnv_hasher = FnvHasher::default();
+        fnv_hasher.write(&content);
+
+        let file_name = match source.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) => format!("{:016x}.{}", fnv_hasher.finish(), extension),
+            None => format!("{:016x}", fnv_hasher.finish()),
+        };
+
+        let destination = self.media_dir.join(&file_name);
+        if !destination.exists() {
+            fs::write(&destination, content)?;
+        }
+
+        let published = format!("{}/{}", MEDIA_DIR, file_name);
+        self.published
+            .insert(source.to_path_buf(), published.clone());
+        Ok(published)
+    }
+}
+
+/// Load every recipe in the recipe directory, sorted by name.
+pub fn load_recipes(recipe_dir: &Path) -> Result<Vec<Recipe>, anyhow::Error> {
+    Ok(load_recipe_files(recipe_dir)?
+        .into_iter()
+        .map(|(_, recipe)| recipe)
+        .collect())
+}
+
+/// Load every recipe in the recipe directory along with the file it was read
+/// from, sorted by name.
+pub fn load_recipe_files(recipe_dir: &Path) -> Result<Vec<(PathBuf, Recipe)>, anyhow::Error> {
+    let recipe_files: Vec<PathBuf> = walkdir::WalkDir::new(recipe_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().display().to_string().ends_with(".yml"))
+        .map(|e| e.path().to_path_buf())
+        .collect();
+
+    let mut recipes: Vec<(PathBuf, Recipe)> = Vec::with_capacity(recipe_files.len());
+    let mut recipe_ids: HashSet<String> = HashSet::new();
+
+    for recipe_file in recipe_files {
+        let recipe_yaml = fs::read_to_string(&recipe_file)?;
+        let deserialized_recipe: Recipe = serde_yaml::from_str(&recipe_yaml)?;
+
+        if !recipe_ids.insert(deserialized_recipe.id.to_string()) {
+            error!("duplicate recipe id: {}", deserialized_recipe.id);
+            continue;
+        }
+
+        recipes.push((recipe_file, deserialized_recipe));
+    }
+
+    recipes.sort_by(|(_, a), (_, b)| {
+        a.name
+            .localized(None)
+            .unwrap()
+            .cmp(&b.name.localized(None).unwrap())
+    });
+
+    Ok(recipes)
+}
+
+/// Write the index of the terms of a taxonomy kind and a page per term
+/// listing the recipes filed under it or any of its descendants.
 #[allow(clippy::too_many_arguments)]
 fn write_indexes(
     handlebars: &Handlebars,
     base_dir: &Path,
     base_url: Url,
-    group_type: String,
+    kind: TermKind,
+    taxonomy: &Taxonomy,
+    catalog: &Catalog,
+    alternates: &Alternates,
     site: SiteView,
     locale: &str,
-    grouped_recipes: HashMap<String, Vec<(String, String)>>,
+    grouped_recipes: HashMap<String, Vec<LinkPartial>>,
     site_links: &mut HashSet<String>,
 ) -> Result<(), anyhow::Error> {
-    let mut group_links: Vec<(String, String)> = Vec::new();
+    let mut group_links: Vec<LinkPartial> = Vec::new();
+    let group_type = kind.group_type();
+    let group_label = catalog.translate(locale, &format!("{}.label", group_type), &[]);
+    let collation = lookup_or_default(locale);
+
+    for term in taxonomy.terms(kind) {
+        let links = match grouped_recipes.get(&term.id) {
+            Some(links) => links,
+            None => continue,
+        };
+        let mut links = links.clone();
+        links.sort_by(|a, b| collation.compare(&a.label, &b.label));
 
-    for group in grouped_recipes.keys().sorted() {
-        let group_slug: String = slugify!(group);
-        let self_url = base_url.join(&format!("{}/", &group_slug))?;
+        let label = taxonomy.label(kind, &term.id, Some(locale.to_string()));
+        let preface = match &term.description {
+            Some(description) => Some(description.localized(Some(locale.to_string()))?),
+            None => None,
+        };
+        let self_url = base_url.join(&format!("{}/", &term.id))?;
 
-        group_links.push((self_url.to_string(), group.clone()));
+        group_links.push(LinkPartial::new(self_url.as_str(), &label));
         site_links.insert(self_url.to_string());
 
-        if let Some(links) = grouped_recipes.get(group) {
-            let html = handlebars.render(
-                "link_list",
-                &LinkListView {
-                    locale: locale.to_string(),
-                    title: format!("Just Recipes - {} - {}", title(&group_type), title(group)),
-                    links_label: title(group),
-                    links: links.clone(),
-                    site: site.clone(),
-                    self_url: self_url.to_string(),
-                },
-            )?;
+        let html = handlebars.render(
+            "link_list",
+            &LinkListView {
+                locale: locale.to_string(),
+                title: catalog.translate(
+                    locale,
+                    "page.group_item.title",
+                    &[
+                        ("site", site.name.clone()),
+                        ("group", group_label.clone()),
+                        ("item", label.clone()),
+                    ],
+                ),
+                links_label: label,
+                preface,
+                links,
+                site: site.clone(),
+                self_url: self_url.to_string(),
+                alternates: alternates.links(self_url.as_str()),
+            },
+        )?;
 
-            let destination = Path::new(&base_dir).join(group_slug).join("index.html");
-            fs::create_dir_all(destination.parent().unwrap())?;
-            fs::write(&destination, html)?;
-        }
+        let destination = Path::new(&base_dir).join(&term.id).join("index.html");
+        fs::create_dir_all(destination.parent().unwrap())?;
+        fs::write(&destination, html)?;
     }
+    group_links.sort_by(|a, b| collation.compare(&a.label, &b.label));
 
     site_links.insert(base_url.to_string());
 
@@ -428,19 +1083,267 @@ fn write_indexes(
         "link_list",
         &LinkListView {
             locale: locale.to_string(),
-            title: format!("Just Recipes - {}", title(&group_type)),
-            links_label: title(&group_type),
+            title: catalog.translate(
+                locale,
+                "page.group.title",
+                &[("site", site.name.clone()), ("group", group_label.clone())],
+            ),
+            links_label: group_label,
+            preface: None,
             links: group_links,
             site,
             self_url: base_url.to_string(),
+            alternates: alternates.links(base_url.as_str()),
         },
     )?;
 
+    fs::create_dir_all(base_dir)?;
     let index_destination = Path::new(&base_dir).join("index.html");
     fs::write(&index_destination, index_html)?;
     Ok(())
 }
 
+/// Write the index of the tags of a kind with their recipe counts and a page
+/// per tag listing its recipes.
+#[allow(clippy::too_many_arguments)]
+fn write_tag_indexes(
+    handlebars: &Handlebars,
+    base_dir: &Path,
+    base_url: Url,
+    kind: TagKind,
+    catalog: &Catalog,
+    alternates: &Alternates,
+    site: SiteView,
+    locale: &str,
+    tagged_recipes: HashMap<String, (String, Vec<LinkPartial>)>,
+    site_links: &mut HashSet<String>,
+) -> Result<(), anyhow::Error> {
+    let mut tag_links: Vec<LinkPartial> = Vec::with_capacity(tagged_recipes.len());
+    let group_label = catalog.translate(locale, &format!("{}.label", kind.group_type()), &[]);
+    let collation = lookup_or_default(locale);
+
+    for (key, (label, mut links)) in tagged_recipes {
+        let label = title(&label);
+        let self_url = base_url.join(&format!("{}/", key))?;
+        links.sort_by(|a, b| collation.compare(&a.label, &b.label));
+
+        let mut tag_link = LinkPartial::new(self_url.as_str(), &label);
+        tag_link.count = Some(links.len());
+        tag_links.push(tag_link);
+        site_links.insert(self_url.to_string());
+
+        let html = handlebars.render(
+            "link_list",
+            &LinkListView {
+                locale: locale.to_string(),
+                title: catalog.translate(
+                    locale,
+                    "page.group_item.title",
+                    &[
+                        ("site", site.name.clone()),
+                        ("group", group_label.clone()),
+                        ("item", label.clone()),
+                    ],
+                ),
+                links_label: label,
+                preface: None,
+                links,
+                site: site.clone(),
+                self_url: self_url.to_string(),
+                alternates: alternates.links(self_url.as_str()),
+            },
+        )?;
+
+        let destination = Path::new(&base_dir).join(&key).join("index.html");
+        fs::create_dir_all(destination.parent().unwrap())?;
+        fs::write(&destination, html)?;
+    }
+    tag_links.sort_by(|a, b| collation.compare(&a.label, &b.label));
+
+    site_links.insert(base_url.to_string());
+
+    let index_html = handlebars.render(
+        "link_list",
+        &LinkListView {
+            locale: locale.to_string(),
+            title: catalog.translate(
+                locale,
+                "page.group.title",
+                &[("site", site.name.clone()), ("group", group_label.clone())],
+            ),
+            links_label: group_label,
+            preface: None,
+            links: tag_links,
+            site,
+            self_url: base_url.to_string(),
+            alternates: alternates.links(base_url.as_str()),
+        },
+    )?;
+
+    fs::create_dir_all(base_dir)?;
+    fs::write(base_dir.join("index.html"), index_html)?;
+    Ok(())
+}
+
+/// Pair each keyword or piece of equipment of a recipe with the URL of its tag
+/// page, or an empty URL when it has no tag or tag pages are disabled.
+fn tag_links(
+    kind: TagKind,
+    values: &[LocalizedString],
+    locale_url: Option<&Url>,
+    locale: &str,
+) -> Result<Vec<(String, String)>, anyhow::Error> {
+    let mut links: Vec<(String, String)> = Vec::with_capacity(values.len());
+    for value in values {
+        let url = match (locale_url, kind.tag(value, locale)?) {
+            (Some(locale_url), Some((key, _))) => locale_url
+                .join(&format!("{}/{}/", kind.group_type(), key))?
+                .to_string(),
+            _ => String::new(),
+        };
+        links.push((value.localized(Some(locale.to_string()))?, url));
+    }
+    Ok(links)
+}
+
+/// The nutrition facts of a recipe as the label and amount per serving of
+/// each nutrient it has.
+fn nutrition_facts(
+    nutrition: Option<&Nutrition>,
+    catalog: &Catalog,
+    locale: &str,
+) -> Vec<(String, String)> {
+    let format_locale = lookup_or_default(locale);
+    let values = match nutrition {
+        Some(nutrition) => nutrients(nutrition),
+        None => return Vec::new(),
+    };
+    NUTRIENT_COLUMNS
+        .iter()
+        .zip(values)
+        .filter_map(|(name, value)| {
+            let amount = match *name {
+                "calories" => format_locale.format_number(value?.round()),
+                "cholesterol" | "sodium" => {
+                    format!("{} mg", format_locale.format_number(value?.round()))
+                }
+                _ => format!(
+                    "{} g",
+                    format_locale.format_number((value? * 10.0).round() / 10.0)
+                ),
+            };
+            Some((
+                catalog.translate(locale, &format!("nutrition.{}", name), &[]),
+                amount,
+            ))
+        })
+        .collect()
+}
+
+/// Pair each ingredient of a recipe with the URL of the recipe it is made by
+/// following, or an empty URL.
+fn ingredient_links(
+    recipe: &Recipe,
+    recipes: &[Recipe],
+    locale_url: &Url,
+    locale: &str,
+) -> Result<Vec<(String, String)>, anyhow::Error> {
+    let mut links: Vec<(String, String)> = Vec::with_capacity(recipe.ingredients.len());
+    for line in &recipe.ingredients {
+        let url = match line.recipe {
+            Some(id) => sub_recipe_link(recipes, id, locale_url, locale)?.map(|(_, url)| url),
+            None => None,
+        };
+        links.push((
+            localize_quantity(
+                &line.text.localized(Some(locale.to_string()))?,
+                lookup_or_default(locale),
+            ),
+            url.unwrap_or_default(),
+        ));
+    }
+    Ok(links)
+}
+
+/// The name and URL of a recipe another recipe is made with, when it is
+/// published in the locale.
+fn sub_recipe_link(
+    recipes: &[Recipe],
+    id: Uuid,
+    locale_url: &Url,
+    locale: &str,
+) -> Result<Option<(String, String)>, anyhow::Error> {
+    let sub_recipe = match recipes.iter().find(|recipe| recipe.id == id) {
+        Some(sub_recipe) if sub_recipe.locales.iter().any(|l| l == locale) => sub_recipe,
+        _ => return Ok(None),
+    };
+    let slug = sub_recipe.slug.localized(Some(locale.to_string()))?;
+    Ok(Some((
+        sub_recipe.name.localized(Some(locale.to_string()))?,
+        locale_url.join(&format!("{}/", slug))?.to_string(),
+    )))
+}
+
+/// Write an alphabetical index of the recipes of a locale, grouped by the
+/// letter they are listed under in the locale.
+#[allow(clippy::too_many_arguments)]
+fn write_letter_index(
+    handlebars: &Handlebars,
+    base_dir: &Path,
+    base_url: Url,
+    catalog: &Catalog,
+    alternates: &Alternates,
+    site: SiteView,
+    locale: &str,
+    recipe_links: &[LinkPartial],
+    site_links: &mut HashSet<String>,
+) -> Result<(), anyhow::Error> {
+    let collation = lookup_or_default(locale);
+
+    let mut letters: Vec<LetterPartial> = Vec::new();
+    for link in recipe_links {
+        let letter = collation.index_letter(&link.label);
+        match letters
+            .iter_mut()
+            .find(|existing| existing.letter == letter)
+        {
+            Some(existing) => existing.links.push(link.clone()),
+            None => letters.push(LetterPartial {
+                anchor: match letter.as_str() {
+                    "#" => String::from("letter-0-9"),
+                    _ => format!("letter-{}", letter.to_lowercase()),
+                },
+                letter,
+                links: vec![link.clone()],
+            }),
+        }
+    }
+    letters.sort_by(|a, b| collation.compare(&a.letter, &b.letter));
+
+    site_links.insert(base_url.to_string());
+
+    let html = handlebars.render(
+        "letter_index",
+        &LetterIndexView {
+            locale: locale.to_string(),
+            title: catalog.translate(
+                locale,
+                "page.letter_index.title",
+                &[("site", site.name.clone())],
+            ),
+            links_label: catalog.translate(locale, "letter_index.label", &[]),
+            letters,
+            site,
+            self_url: base_url.to_string(),
+            alternates: alternates.links(base_url.as_str()),
+        },
+    )?;
+
+    fs::create_dir_all(base_dir)?;
+    fs::write(base_dir.join("index.html"), html)?;
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 fn write_oembed(
     handlebars: &Handlebars,
@@ -449,7 +1352,8 @@ fn write_oembed(
     recipe: &RecipePartial,
     site: SiteView,
     locale: &str,
-    has_images: bool,
+    thumbnail_url: Option<String>,
+    share_image: Option<ShareImage>,
 ) -> Result<(), anyhow::Error> {
     let oembed_html = handlebars.render(
         "oembed",
@@ -457,16 +1361,9 @@ fn write_oembed(
             locale: locale.to_string(),
             title: recipe.name.clone(),
             recipe: recipe.clone(),
-            site,
+            site: site.clone(),
             recipe_url: base_url.to_string(),
-            image_url: match has_images {
-                false => None,
-                true => Some(
-                    base_url
-                        .join(&format!("{}_thumbnail.jpg", recipe.id))?
-                        .to_string(),
-                ),
-            },
+            image_url: thumbnail_url,
         },
     )?;
 
@@ -477,17 +1374,17 @@ fn write_oembed(
         response_type: String::from("rich"),
         version: String::from("1.0"),
         title: Some(String::from("A recipe")),
-        author_name: Some(String::from("Anonymous")),
-        author_url: Some(String::from("https://justrecipes.blog/")),
-        provider_name: Some(String::from("Just Recipes Blog")),
-        provider_url: Some(String::from("https://justrecipes.blog/")),
-        html: format!("<iframe width=\"100%\" height=\"270\" scrolling=\"no\" frameborder=\"no\" src=\"{}\"></iframe>", base_url.join("oembed.html")?.to_string()),
+        author_name: site.authors.first().map(|author| author.name.clone()),
+        author_url: site.authors.first().and_then(|author| author.url.clone()),
+        provider_name: Some(site.name.clone()),
+        provider_url: Some(site.public_url.clone()),
+        html: format!("<iframe width=\"100%\" height=\"270\" scrolling=\"no\" frameborder=\"no\" src=\"{}\"></iframe>", base_url.join("oembed.html")?),
         width: Some(550),
         height: Some(270),
         cache_age: Some(String::from("3153600000")),
-        thumbnail_url: Some(String::from("3153600000")),
-        thumbnail_width: Some(200),
-        thumbnail_height: Some(200),
+        thumbnail_url: share_image.as_ref().map(|image| image.url.clone()),
+        thumbnail_width: share_image.as_ref().map(|image| image.width),
+        thumbnail_height: share_image.as_ref().map(|image| image.height),
     })?;
 
     let json_destination = Path::new(&base_dir).join("oembed.json");
@@ -0,0 +1,500 @@
+use anyhow::anyhow;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::ingredient::canonical_equipment;
+use crate::locale::{Locale, Units};
+use crate::model::{LocalizedString, Step};
+use crate::patch;
+use crate::site::load_recipe_files;
+
+const SECOND_WORDS: &[&str] = &["sec", "secs", "second", "seconds"];
+const MINUTE_WORDS: &[&str] = &["min", "mins", "minute", "minutes"];
+const HOUR_WORDS: &[&str] = &["hr", "hrs", "hour", "hours"];
+
+const NUMBER_WORDS: &[(&str, f64)] = &[
+    ("a", 1.0),
+    ("an", 1.0),
+    ("one", 1.0),
+    ("two", 2.0),
+    ("three", 3.0),
+    ("four", 4.0),
+    ("five", 5.0),
+    ("six", 6.0),
+    ("ten", 10.0),
+    ("fifteen", 15.0),
+    ("twenty", 20.0),
+    ("thirty", 30.0),
+];
+
+/// How long a step takes, written as `15m` or as the range `15m-20m`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DurationRange {
+    pub min: Duration,
+    pub max: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureScale {
+    Fahrenheit,
+    Celsius,
+}
+
+/// A cooking temperature, written as `350F` or `180C`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Temperature {
+    pub degrees: f64,
+    pub scale: TemperatureScale,
+}
+
+/// The structured values of a step found in its text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepDetails {
+    pub duration: Option<DurationRange>,
+    pub temperature: Option<Temperature>,
+    pub equipment: Vec<LocalizedString>,
+}
+
+impl DurationRange {
+    /// The duration written in a locale, such as `15 minutes`, and the end of
+    /// the range when there is one.
+    pub fn localized(&self, locale: &Locale) -> (String, Option<String>) {
+        (
+            locale.format_duration(self.min),
+            self.max.map(|max| locale.format_duration(max)),
+        )
+    }
+}
+
+impl fmt::Display for DurationRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", humantime::format_duration(self.min))?;
+        if let Some(max) = self.max {
+            write!(f, "-{}", humantime::format_duration(max))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DurationRange {
+    type Err = anyhow::Error;
+
+    /// Parse `15m`, `1h 15m` or a range such as `15m-20m` or `15-20 minutes`.
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let (min, max) = match src.split_once('-') {
+            Some((min, max)) => {
+                let min = min.trim();
+                let max = max.trim();
+                match min.chars().all(|c| c.is_ascii_digit()) {
+                    true => (
+                        format!(
+                            "{}{}",
+                            min,
+                            max.trim_start_matches(|c: char| c.is_ascii_digit())
+                        ),
+                        Some(max),
+                    ),
+                    false => (min.to_string(), Some(max)),
+                }
+            }
+            None => (src.trim().to_string(), None),
+        };
+
+        let min = humantime::parse_duration(&min).map_err(|err| anyhow!("{}: {}", src, err))?;
+        let max = match max {
+            Some(max) => {
+                Some(humantime::parse_duration(max).map_err(|err| anyhow!("{}: {}", src, err))?)
+            }
+            None => None,
+        };
+        if max.map(|max| max < min).unwrap_or(false) {
+            return Err(anyhow!("{}: the range ends before it starts", src));
+        }
+        Ok(DurationRange { min, max })
+    }
+}
+
+impl Temperature {
+    pub fn fahrenheit(&self) -> f64 {
+        match self.scale {
+            TemperatureScale::Fahrenheit => self.degrees,
+            TemperatureScale::Celsius => self.degrees * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    pub fn celsius(&self) -> f64 {
+        match self.scale {
+            TemperatureScale::Fahrenheit => (self.degrees - 32.0) * 5.0 / 9.0,
+            TemperatureScale::Celsius => self.degrees,
+        }
+    }
+
+    /// The temperature in the scale of a locale, rounded to the degree.
+    pub fn localized(&self, locale: &Locale) -> String {
+        match locale.units {
+            Units::Imperial => format!("{} °F", locale.format_number(self.fahrenheit().round())),
+            Units::Metric => format!("{} °C", locale.format_number(self.celsius().round())),
+        }
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.scale {
+            TemperatureScale::Fahrenheit => write!(f, "{}F", self.degrees),
+            TemperatureScale::Celsius => write!(f, "{}C", self.degrees),
+        }
+    }
+}
+
+impl FromStr for Temperature {
+    type Err = anyhow::Error;
+
+    /// Parse `350F`, `350 °F` or `180C`.
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let text = src.trim();
+        let (degrees, scale) = match text.chars().last().map(|c| c.to_ascii_uppercase()) {
+            Some('F') => (&text[..text.len() - 1], TemperatureScale::Fahrenheit),
+            Some('C') => (&text[..text.len() - 1], TemperatureScale::Celsius),
+            _ => return Err(anyhow!("{}: the temperature must end in F or C", src)),
+        };
+        let degrees: f64 = degrees
+            .trim_end()
+            .trim_end_matches('°')
+            .trim_end()
+            .parse()
+            .map_err(|_| anyhow!("{}: invalid temperature", src))?;
+        Ok(Temperature { degrees, scale })
+    }
+}
+
+macro_rules! serde_from_str {
+    ($type:ty) => {
+        impl Serialize for $type {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_str(&self.to_string())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let text = String::deserialize(deserializer)?;
+                text.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+serde_from_str!(DurationRange);
+serde_from_str!(Temperature);
+
+impl StepDetails {
+    /// Propose structured values for a step from its en_US text. The
+    /// equipment of the step is the recipe equipment the text mentions.
+    pub fn extract(text: &str, equipment: &[LocalizedString]) -> Self {
+        let normalized = text.to_lowercase().replace(['–', '—'], "-");
+        let words: Vec<&str> = normalized
+            .split_whitespace()
+            .map(|word| word.trim_matches([',', '.', ';', ':', '(', ')', '!', '?']))
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        let padded = format!(" {} ", words.join(" "));
+        let mentioned: Vec<LocalizedString> = equipment
+            .iter()
+            .filter(|item| {
+                let name = canonical_equipment(&item.localized(None).unwrap_or_default());
+                !name.is_empty() && padded.contains(&format!(" {} ", name))
+            })
+            .cloned()
+            .collect();
+
+        StepDetails {
+            duration: (0..words.len()).find_map(|index| extract_duration(&words[index..])),
+            temperature: (0..words.len()).find_map(|index| extract_temperature(&words[index..])),
+            equipment: mentioned,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.duration.is_none() && self.temperature.is_none() && self.equipment.is_empty()
+    }
+}
+
+/// A count at the start of the words, such as `15`, `one` or the range
+/// `15-20`, and the number of words it spans.
+fn extract_count(words: &[&str]) -> Option<(f64, Option<f64>, usize)> {
+    let number = |word: &str| {
+        word.parse::<f64>().ok().or_else(|| {
+            NUMBER_WORDS
+                .iter()
+                .find(|(name, _)| *name == word)
+                .map(|(_, value)| *value)
+        })
+    };
+
+    let first = *words.first()?;
+    if let Some((min, max)) = first.split_once('-') {
+        return Some((min.parse().ok()?, Some(max.parse().ok()?), 1));
+    }
+    let min = number(first)?;
+    match (words.get(1), words.get(2).and_then(|word| number(word))) {
+        (Some(&"to"), Some(max)) | (Some(&"-"), Some(max)) | (Some(&"or"), Some(max)) => {
+            Some((min, Some(max), 3))
+        }
+        _ => Some((min, None, 1)),
+    }
+}
+
+fn unit_seconds(word: &str) -> Option<f64> {
+    if SECOND_WORDS.contains(&word) {
+        Some(1.0)
+    } else if MINUTE_WORDS.contains(&word) {
+        Some(60.0)
+    } else if HOUR_WORDS.contains(&word) {
+        Some(3600.0)
+    } else {
+        None
+    }
+}
+
+/// A duration at the start of the words, such as `15-20 minutes` or `1 hour
+/// and 15 min`.
+fn extract_duration(words: &[&str]) -> Option<DurationRange> {
+    let (min, max, length) = extract_count(words)?;
+    let unit = unit_seconds(words.get(length)?)?;
+    let mut min = min * unit;
+    let mut max = max.map(|max| max * unit);
+
+    if HOUR_WORDS.contains(&words[length]) {
+        let rest = &words[length + 1..];
+        let rest = match rest.first() {
+            Some(&"and") => &rest[1..],
+            _ => rest,
+        };
+        if let Some((minutes, None, count_length)) = extract_count(rest) {
+            if rest
+                .get(count_length)
+                .map(|word| MINUTE_WORDS.contains(word))
+                .unwrap_or(false)
+            {
+                min += minutes * 60.0;
+                max = max.map(|max| max + minutes * 60.0);
+            }
+        }
+    }
+
+    Some(DurationRange {
+        min: Duration::from_secs(min.round() as u64),
+        max: max.map(|max| Duration::from_secs(max.round() as u64)),
+    })
+}
+
+/// A temperature at the start of the words, such as `325 degrees fahrenheit`,
+/// `180°c` or `325 f`. Degrees without a scale are taken as Fahrenheit.
+fn extract_temperature(words: &[&str]) -> Option<Temperature> {
+    let first = *words.first()?;
+    let split = first
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(first.len());
+    let degrees: f64 = first[..split].parse().ok()?;
+
+    let scale = |word: &str, after_degrees: bool| match word {
+        "f" | "°f" | "fahrenheit" => Some(TemperatureScale::Fahrenheit),
+        "c" if after_degrees => Some(TemperatureScale::Celsius),
+        "°c" | "celsius" | "centigrade" => Some(TemperatureScale::Celsius),
+        _ => None,
+    };
+    let scale_after_degrees = |word: Option<&&str>| {
+        word.and_then(|word| scale(word, true))
+            .unwrap_or(TemperatureScale::Fahrenheit)
+    };
+
+    let scale = match &first[split..] {
+        "" => match *words.get(1)? {
+            "degrees" | "degree" | "°" => Some(scale_after_degrees(words.get(2))),
+            word => scale(word, false),
+        },
+        "°" => Some(scale_after_degrees(words.get(1))),
+        suffix => scale(suffix, false),
+    }?;
+
+    Some(Temperature { degrees, scale })
+}
+
+/// Print the structured values proposed for the steps of every recipe that
+/// do not have any, and write them to the recipe files when `write` is set.
+/// Only the updated steps are rewritten, the rest of each recipe file is
+/// left as it was written.
+pub fn extract_steps(recipe_dir: &Path, write: bool) -> Result<(), anyhow::Error> {
+    for (recipe_file, mut recipe) in load_recipe_files(recipe_dir)? {
+        let equipment = recipe.equipment.clone().unwrap_or_default();
+        let mut proposed: Vec<String> = Vec::new();
+
+        for (stage_index, stage) in recipe.stages.iter_mut().enumerate() {
+            for (step_index, step) in stage.steps.iter_mut().enumerate() {
+                if step.has_details() {
+                    continue;
+                }
+                let details = StepDetails::extract(&step.text.localized(None)?, &equipment);
+                if details.is_empty() {
+                    continue;
+                }
+
+                let mut values: Vec<String> = Vec::new();
+                if let Some(duration) = &details.duration {
+                    values.push(format!("duration {}", duration));
+                }
+                if let Some(temperature) = &details.temperature {
+                    values.push(format!("temperature {}", temperature));
+                }
+                for item in &details.equipment {
+                    values.push(format!("equipment {}", item.localized(None)?));
+                }
+                println!(
+                    "{} stages[{}].steps[{}]: {}",
+                    recipe_file.display(),
+                    stage_index,
+                    step_index,
+                    values.join(", ")
+                );
+
+                set_details(step, details);
+                proposed.push(format!("stages[{}].steps[{}]", stage_index, step_index));
+            }
+        }
+
+        if write && !proposed.is_empty() {
+            let value = serde_yaml::to_value(&recipe)?;
+            let mut document = std::fs::read_to_string(&recipe_file)?;
+            for path in &proposed {
+                document = patch::replace(&document, path, patch::value_at(&value, path)?)
+                    .map_err(|err| anyhow!("{}: {}", recipe_file.display(), err))?;
+            }
+            std::fs::write(&recipe_file, document)?;
+            println!(
+                "updated {} steps in {}",
+                proposed.len(),
+                recipe_file.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn set_details(step: &mut Step, details: StepDetails) {
+    step.duration = details.duration;
+    step.temperature = details.temperature;
+    step.equipment = details.equipment;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(value: u64) -> Duration {
+        Duration::from_secs(value * 60)
+    }
+
+    #[test]
+    fn parse_ok() {
+        assert_eq!(
+            "15m-20m".parse::<DurationRange>().unwrap(),
+            DurationRange {
+                min: minutes(15),
+                max: Some(minutes(20))
+            }
+        );
+        assert_eq!(
+            "15-20 minutes".parse::<DurationRange>().unwrap(),
+            "15m-20m".parse::<DurationRange>().unwrap()
+        );
+        assert_eq!(
+            "1h 15m".parse::<DurationRange>().unwrap().to_string(),
+            "1h 15m"
+        );
+        assert!("20m-15m".parse::<DurationRange>().is_err());
+
+        let temperature: Temperature = "350 °F".parse().unwrap();
+        assert_eq!(temperature.to_string(), "350F");
+        assert_eq!(
+            temperature.localized(crate::locale::lookup("fr_FR").unwrap()),
+            "177 °C"
+        );
+        assert_eq!(
+            "180c".parse::<Temperature>().unwrap(),
+            Temperature {
+                degrees: 180.0,
+                scale: TemperatureScale::Celsius
+            }
+        );
+        assert!("350".parse::<Temperature>().is_err());
+    }
+
+    #[test]
+    fn extract_ok() {
+        let equipment = vec![
+            LocalizedString::new("5 quart dutch oven or similar stove top pot"),
+            LocalizedString::new("pan"),
+        ];
+
+        let details = StepDetails::extract(
+            "Reduce heat to medium-low, cover, and cook until chicken reads 160 degrees fahrenheit, flipping chicken half-way. This takes about 15-20 minutes.",
+            &equipment,
+        );
+        assert_eq!(
+            details.duration,
+            Some(DurationRange {
+                min: minutes(15),
+                max: Some(minutes(20))
+            })
+        );
+        assert_eq!(details.temperature.unwrap().to_string(), "160F");
+        assert!(details.equipment.is_empty());
+
+        let details = StepDetails::extract(
+            "Bake at 325 degrees fahrenheit for 1 hour and 15 min or until tops are golden.",
+            &equipment,
+        );
+        assert_eq!(details.duration.unwrap().min, minutes(75));
+
+        let details = StepDetails::extract(
+            "Set dutch oven to medium-high heat. This takes up to 5 minutes.",
+            &equipment,
+        );
+        assert_eq!(details.duration.unwrap().min, minutes(5));
+        assert_eq!(details.equipment, vec![equipment[0].clone()]);
+
+        for (text, expected) in [
+            ("Place the dough in a 325 F oven.", Some("325F")),
+            ("Heat the oil to 180°C.", Some("180C")),
+            ("Add 2 cups of flour.", None),
+        ] {
+            assert_eq!(
+                StepDetails::extract(text, &[])
+                    .temperature
+                    .map(|temperature| temperature.to_string())
+                    .as_deref(),
+                expected
+            );
+        }
+        assert_eq!(
+            StepDetails::extract("Cook until fragrant, about a minute.", &[]).duration,
+            Some(DurationRange {
+                min: minutes(1),
+                max: None
+            })
+        );
+        assert!(StepDetails::extract("Chop celery into 1/2 inch pieces", &equipment).is_empty());
+    }
+}
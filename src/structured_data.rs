@@ -0,0 +1,308 @@
+use serde::Serialize;
+use url::Url;
+
+use crate::config::Author;
+use crate::locale::hreflang;
+use crate::model::{Nutrition, Recipe, RecipePartial};
+use crate::when::duration_iso8601;
+
+const SCHEMA_CONTEXT: &str = "https://schema.org";
+
+/// A schema.org Recipe, published as JSON-LD in recipe pages.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecipeSchema {
+    #[serde(rename = "@context")]
+    pub context: &'static str,
+    #[serde(rename = "@type")]
+    pub schema_type: &'static str,
+    pub name: String,
+    pub url: String,
+    pub in_language: String,
+    pub date_published: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub image: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub author: Vec<PersonSchema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keywords: Option<String>,
+    pub recipe_category: String,
+    pub recipe_cuisine: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipe_yield: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prep_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cook_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_time: Option<String>,
+    pub recipe_ingredient: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool: Vec<ThingSchema>,
+    pub recipe_instructions: Vec<HowToSectionSchema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nutrition: Option<NutritionSchema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video: Option<VideoSchema>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PersonSchema {
+    #[serde(rename = "@type")]
+    pub schema_type: &'static str,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// A named thing such as a `HowToTool`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThingSchema {
+    #[serde(rename = "@type")]
+    pub schema_type: &'static str,
+    pub name: String,
+}
+
+/// The steps of a recipe stage.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HowToSectionSchema {
+    #[serde(rename = "@type")]
+    pub schema_type: &'static str,
+    pub name: String,
+    pub item_list_element: Vec<HowToStepSchema>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HowToStepSchema {
+    #[serde(rename = "@type")]
+    pub schema_type: &'static str,
+    pub text: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NutritionSchema {
+    #[serde(rename = "@type")]
+    pub schema_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serving_size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calories: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fat_content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saturated_fat_content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cholesterol_content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sodium_content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub carbohydrate_content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fiber_content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sugar_content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protein_content: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoSchema {
+    #[serde(rename = "@type")]
+    pub schema_type: &'static str,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub thumbnail_url: String,
+    pub upload_date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embed_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+}
+
+impl RecipeSchema {
+    /// The structured data of a recipe in a locale. The partial provides the
+    /// localized text and the recipe the values that are not localized, and
+    /// the nutrition facts are those set in or computed for the recipe.
+    pub fn new(
+        recipe: &Recipe,
+        partial: &RecipePartial,
+        locale: &str,
+        self_url: &Url,
+        images: Vec<String>,
+        site_authors: &[Author],
+        nutrition: Option<&Nutrition>,
+    ) -> Result<Self, anyhow::Error> {
+        let author: Vec<PersonSchema> = match &recipe.author {
+            Some(name) => vec![PersonSchema {
+                schema_type: "Person",
+                name: name.clone(),
+                url: None,
+            }],
+            None => site_authors
+                .iter()
+                .map(|author| PersonSchema {
+                    schema_type: "Person",
+                    name: author.name.clone(),
+                    url: author.url.clone(),
+                })
+                .collect(),
+        };
+
+        let video = match &recipe.video {
+            Some(video) => Some(VideoSchema {
+                schema_type: "VideoObject",
+                name: video.name.localized(Some(locale.to_string()))?,
+                description: match &video.description {
+                    Some(description) => Some(description.localized(Some(locale.to_string()))?),
+                    None => None,
+                },
+                thumbnail_url: video.thumbnail_url.clone(),
+                upload_date: video.upload_date.clone(),
+                content_url: video.content_url.clone(),
+                embed_url: video.embed_url.clone(),
+                duration: video.duration.map(duration_iso8601),
+            }),
+            None => None,
+        };
+
+        Ok(RecipeSchema {
+            context: SCHEMA_CONTEXT,
+            schema_type: "Recipe",
+            name: partial.name.clone(),
+            url: self_url.to_string(),
+            in_language: hreflang(locale),
+            date_published: partial.published.clone(),
+            description: partial.description.clone(),
+            image: images,
+            author,
+            keywords: match partial.keywords.is_empty() {
+                true => None,
+                false => Some(partial.keywords.join(", ")),
+            },
+            recipe_category: partial.category.clone(),
+            recipe_cuisine: partial.cuisine.clone(),
+            recipe_yield: recipe.servings.map(|servings| servings.to_string()),
+            prep_time: partial.sd_prep_time.clone(),
+            cook_time: partial.sd_cook_time.clone(),
+            total_time: partial.sd_total_time.clone(),
+            recipe_ingredient: partial.ingredients.clone(),
+            tool: partial
+                .equipment
+                .iter()
+                .map(|item| ThingSchema {
+                    schema_type: "HowToTool",
+                    name: item.clone(),
+                })
+                .collect(),
+            recipe_instructions: partial
+                .stages
+                .iter()
+                .map(|stage| HowToSectionSchema {
+                    schema_type: "HowToSection",
+                    name: stage.name.clone(),
+                    item_list_element: stage
+                        .steps
+                        .iter()
+                        .map(|step| HowToStepSchema {
+                            schema_type: "HowToStep",
+                            text: step.text.clone(),
+                            url: format!("{}#{}", self_url, step.id),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            nutrition: nutrition.map(NutritionSchema::new),
+            video,
+        })
+    }
+
+    /// The JSON-LD document, safe to embed in a `script` element.
+    pub fn to_json_ld(&self) -> Result<String, anyhow::Error> {
+        Ok(serde_json::to_string_pretty(self)?.replace("</", "<\\/"))
+    }
+}
+
+impl NutritionSchema {
+    pub fn new(nutrition: &Nutrition) -> Self {
+        let amount = |value: Option<f64>, unit: &str| {
+            value.map(|value| format!("{} {}", (value * 10.0).round() / 10.0, unit))
+        };
+        NutritionSchema {
+            schema_type: "NutritionInformation",
+            serving_size: Some(String::from("1 serving")),
+            calories: amount(nutrition.calories, "calories"),
+            fat_content: amount(nutrition.fat, "g"),
+            saturated_fat_content: amount(nutrition.saturated_fat, "g"),
+            cholesterol_content: amount(nutrition.cholesterol, "mg"),
+            sodium_content: amount(nutrition.sodium, "mg"),
+            carbohydrate_content: amount(nutrition.carbohydrate, "g"),
+            fiber_content: amount(nutrition.fiber, "g"),
+            sugar_content: amount(nutrition.sugar, "g"),
+            protein_content: amount(nutrition.protein, "g"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::taxonomy::Taxonomy;
+
+    #[test]
+    fn recipe_schema_ok() {
+        let mut recipe = Recipe::init(None, Some(String::from("Back\\slash \"pie\"")), true);
+        recipe.servings = Some(4);
+        recipe.nutrition = Some(Nutrition {
+            calories: Some(240.04),
+            protein: Some(12.0),
+            ..Nutrition::default()
+        });
+        let partial = recipe
+            .to_partial(None, &[], Vec::new(), None, &Taxonomy::default())
+            .unwrap();
+        let self_url = Url::parse("https://example.com/en_US/pie/").unwrap();
+
+        let schema = RecipeSchema::new(
+            &recipe,
+            &partial,
+            "en_US",
+            &self_url,
+            vec![String::from("https://example.com/media/pie.jpg")],
+            &[Author {
+                name: String::from("Jo"),
+                url: None,
+            }],
+            recipe.nutrition.as_ref(),
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&schema.to_json_ld().unwrap()).unwrap();
+
+        assert_eq!(json["@type"], "Recipe");
+        assert_eq!(json["name"], "Back\\slash \"pie\"");
+        assert_eq!(json["inLanguage"], "en-US");
+        assert_eq!(json["recipeYield"], "4");
+        assert_eq!(json["author"][0]["name"], "Jo");
+        assert_eq!(json["recipeInstructions"][0]["@type"], "HowToSection");
+        assert_eq!(json["recipeInstructions"][0]["name"], "Cook");
+        assert_eq!(
+            json["recipeInstructions"][0]["itemListElement"][0]["@type"],
+            "HowToStep"
+        );
+        assert_eq!(
+            json["recipeInstructions"][0]["itemListElement"][1]["url"],
+            "https://example.com/en_US/pie/#stage-1-step-2"
+        );
+        assert_eq!(json["nutrition"]["calories"], "240 calories");
+        assert_eq!(json["nutrition"]["proteinContent"], "12 g");
+        assert!(json.get("video").is_none());
+    }
+}
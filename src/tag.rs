@@ -0,0 +1,77 @@
+use slugify::slugify;
+
+use crate::ingredient::{canonical_equipment, Ingredient};
+use crate::model::{LocalizedString, Recipe};
+
+/// The free text lists of a recipe that are browsable as tag pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagKind {
+    Keyword,
+    Equipment,
+    Ingredient,
+}
+
+impl TagKind {
+    pub const ALL: [TagKind; 3] = [TagKind::Keyword, TagKind::Equipment, TagKind::Ingredient];
+
+    /// The directory of the tag pages for the kind, also used as the prefix of
+    /// its message catalog keys.
+    pub fn group_type(&self) -> &'static str {
+        match self {
+            TagKind::Keyword => "keywords",
+            TagKind::Equipment => "equipment",
+            TagKind::Ingredient => "ingredients",
+        }
+    }
+
+    /// The label of a keyword, piece of equipment or ingredient, with measures
+    /// and descriptors removed from equipment and ingredients.
+    pub fn label(&self, text: &str) -> String {
+        match self {
+            TagKind::Keyword => text.trim().to_lowercase(),
+            TagKind::Equipment => canonical_equipment(text),
+            TagKind::Ingredient => Ingredient::parse(text).canonical_name(),
+        }
+    }
+
+    /// The (key, label) of a keyword, piece of equipment or ingredient in a
+    /// locale. Keys are derived from the en_US text so that a tag has the same
+    /// URL in every locale.
+    pub fn tag(
+        &self,
+        value: &LocalizedString,
+        locale: &str,
+    ) -> Result<Option<(String, String)>, anyhow::Error> {
+        let key: String = slugify!(&self.label(&value.localized(None)?));
+        if key.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some((
+            key,
+            self.label(&value.localized(Some(locale.to_string()))?),
+        )))
+    }
+
+    /// The tags of a recipe in a locale, without duplicates.
+    pub fn tags(
+        &self,
+        recipe: &Recipe,
+        locale: &str,
+    ) -> Result<Vec<(String, String)>, anyhow::Error> {
+        let values: Vec<&LocalizedString> = match self {
+            TagKind::Keyword => recipe.keywords.iter().flatten().collect(),
+            TagKind::Equipment => recipe.equipment.iter().flatten().collect(),
+            TagKind::Ingredient => recipe.ingredients.iter().map(|line| &line.text).collect(),
+        };
+
+        let mut tags: Vec<(String, String)> = Vec::with_capacity(values.len());
+        for value in values {
+            if let Some(tag) = self.tag(value, locale)? {
+                if !tags.iter().any(|(key, _)| *key == tag.0) {
+                    tags.push(tag);
+                }
+            }
+        }
+        Ok(tags)
+    }
+}
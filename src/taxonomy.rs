@@ -0,0 +1,239 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use slugify::slugify;
+use std::{collections::HashSet, fs, path::Path};
+
+use crate::model::{LocalizedString, US_ENGLISH};
+
+/// The default taxonomy file, relative to the working directory.
+pub const TAXONOMY_FILE: &str = "taxonomy.yml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermKind {
+    Category,
+    Cuisine,
+}
+
+impl TermKind {
+    /// The directory of the index pages for the kind, also used as the prefix
+    /// of its message catalog keys.
+    pub fn group_type(&self) -> &'static str {
+        match self {
+            TermKind::Category => "categories",
+            TermKind::Cuisine => "cuisines",
+        }
+    }
+}
+
+/// The categories and cuisines recipes are filed under.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Taxonomy {
+    #[serde(default)]
+    pub categories: Vec<Term>,
+    #[serde(default)]
+    pub cuisines: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Term {
+    /// The id recipes refer to, also used as the URL path of the term pages in
+    /// every locale.
+    pub id: String,
+    pub label: LocalizedString,
+    #[serde(default)]
+    pub description: Option<LocalizedString>,
+    /// The id of a broader term of the same kind. Recipes filed under a term
+    /// are also listed under its parents.
+    #[serde(default)]
+    pub parent: Option<String>,
+}
+
+impl Taxonomy {
+    pub fn load(taxonomy_file: &Path) -> Result<Self, anyhow::Error> {
+        let taxonomy_yaml = fs::read_to_string(taxonomy_file)
+            .map_err(|err| anyhow!("{}: {}", taxonomy_file.display(), err))?;
+        serde_yaml::from_str(&taxonomy_yaml)
+            .map_err(|err| anyhow!("{}: {}", taxonomy_file.display(), err))
+    }
+
+    pub fn terms(&self, kind: TermKind) -> &[Term] {
+        match kind {
+            TermKind::Category => &self.categories,
+            TermKind::Cuisine => &self.cuisines,
+        }
+    }
+
+    pub fn term(&self, kind: TermKind, id: &str) -> Option<&Term> {
+        self.terms(kind).iter().find(|term| term.id == id)
+    }
+
+    /// The localized label of a term, or the id itself for unknown terms.
+    pub fn label(&self, kind: TermKind, id: &str, locale: Option<String>) -> String {
+        self.term(kind, id)
+            .and_then(|term| term.label.localized(locale).ok())
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// The term followed by its parents, nearest first.
+    pub fn lineage(&self, kind: TermKind, id: &str) -> Vec<&Term> {
+        let mut lineage: Vec<&Term> = Vec::new();
+        let mut next = self.term(kind, id);
+        while let Some(term) = next {
+            if lineage.iter().any(|seen| seen.id == term.id) {
+                break;
+            }
+            lineage.push(term);
+            next = term
+                .parent
+                .as_ref()
+                .and_then(|parent| self.term(kind, parent));
+        }
+        lineage
+    }
+
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        for kind in [TermKind::Category, TermKind::Cuisine] {
+            let mut ids: HashSet<&str> = HashSet::new();
+            for term in self.terms(kind) {
+                if term.id.is_empty() || term.id != slugify!(&term.id) {
+                    return Err(anyhow!(
+                        "{} id {:?} must be lowercase letters, numbers and dashes",
+                        kind.group_type(),
+                        term.id
+                    ));
+                }
+                if !ids.insert(&term.id) {
+                    return Err(anyhow!("duplicate {} id {}", kind.group_type(), term.id));
+                }
+                if !term.label.inner.contains_key(US_ENGLISH) {
+                    return Err(anyhow!(
+                        "{} {} must have en_US label",
+                        kind.group_type(),
+                        term.id
+                    ));
+                }
+            }
+
+            for term in self.terms(kind) {
+                let parent = match &term.parent {
+                    Some(parent) => parent,
+                    None => continue,
+                };
+                if self.term(kind, parent).is_none() {
+                    return Err(anyhow!(
+                        "{} {} has unknown parent {}",
+                        kind.group_type(),
+                        term.id,
+                        parent
+                    ));
+                }
+                if self
+                    .lineage(kind, parent)
+                    .iter()
+                    .any(|ancestor| ancestor.id == term.id)
+                {
+                    return Err(anyhow!(
+                        "{} {} is its own ancestor",
+                        kind.group_type(),
+                        term.id
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn taxonomy(categories: &str) -> Taxonomy {
+        serde_yaml::from_str(categories).unwrap()
+    }
+
+    #[test]
+    fn lineage_ok() {
+        let taxonomy = taxonomy(
+            "
+cuisines:
+  - id: american
+    label: American
+  - id: cajun
+    label: Cajun
+    parent: american
+",
+        );
+        let lineage: Vec<&str> = taxonomy
+            .lineage(TermKind::Cuisine, "cajun")
+            .iter()
+            .map(|term| term.id.as_str())
+            .collect();
+        assert_eq!(lineage, vec!["cajun", "american"]);
+        assert_eq!(
+            taxonomy.label(TermKind::Cuisine, "cajun", None),
+            String::from("Cajun")
+        );
+        assert_eq!(
+            taxonomy.label(TermKind::Cuisine, "greek", None),
+            String::from("greek")
+        );
+    }
+
+    #[test]
+    fn validate_err() {
+        assert!(taxonomy(
+            "
+categories:
+  - id: main-dish
+    label: Main dish
+"
+        )
+        .validate()
+        .is_ok());
+        assert_eq!(
+            taxonomy(
+                "
+categories:
+  - id: Main dish
+    label: Main dish
+"
+            )
+            .validate()
+            .unwrap_err()
+            .to_string(),
+            "categories id \"Main dish\" must be lowercase letters, numbers and dashes"
+        );
+        assert_eq!(
+            taxonomy(
+                "
+categories:
+  - id: soup
+    label: Soup
+    parent: stew
+"
+            )
+            .validate()
+            .unwrap_err()
+            .to_string(),
+            "categories soup has unknown parent stew"
+        );
+        assert_eq!(
+            taxonomy(
+                "
+categories:
+  - id: soup
+    label: Soup
+    parent: stew
+  - id: stew
+    label: Stew
+    parent: soup
+"
+            )
+            .validate()
+            .unwrap_err()
+            .to_string(),
+            "categories soup is its own ancestor"
+        );
+    }
+}
@@ -9,6 +9,10 @@ use std::fmt::Write;
 use std::format;
 use std::hash::Hasher;
 
+use crate::catalog::Catalog;
+use crate::locale::{fallback_chain, lookup, TextDirection};
+use crate::model::US_ENGLISH;
+
 #[derive(Debug, Clone, Copy)]
 pub struct LocaleHelper;
 
@@ -36,8 +40,8 @@ impl HelperDef for LocaleHelper {
             Json::Object(ref m) => index
                 .value()
                 .as_str()
-                .and_then(|k| m.get(k))
-                .unwrap_or_else(|| m.get("en_US").unwrap_or(&Json::Null)),
+                .and_then(|k| fallback_chain(k).into_iter().find_map(|l| m.get(l)))
+                .unwrap_or_else(|| m.get(US_ENGLISH).unwrap_or(&Json::Null)),
             _ => &Json::Null,
         };
         if r.strict_mode() && value.is_null() {
@@ -48,6 +52,149 @@ impl HelperDef for LocaleHelper {
     }
 }
 
+/// Translates a message catalog key for the current locale. Hash arguments are
+/// interpolated into the message and `count` selects the plural form:
+/// `{{ t "recipe.steps" count=3 }}`. The locale is taken from a `locale` hash
+/// argument or the `locale` field of the view.
+#[derive(Debug, Clone)]
+pub struct TranslateHelper {
+    pub catalog: Catalog,
+}
+
+impl HelperDef for TranslateHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let key = h
+            .param(0)
+            .and_then(|param| param.value().as_str())
+            .ok_or_else(|| RenderError::new("Param not found for helper \"t\""))?;
+
+        let locale: String = match h.hash_get("locale") {
+            Some(locale) => locale.value().as_str().map(|l| l.to_string()),
+            None => ctx
+                .data()
+                .get("locale")
+                .and_then(|l| l.as_str())
+                .map(|l| l.to_string())
+                .or_else(|| {
+                    rc.evaluate(ctx, "locale")
+                        .ok()
+                        .and_then(|l| l.as_json().as_str().map(|l| l.to_string()))
+                }),
+        }
+        .unwrap_or_else(|| US_ENGLISH.to_string());
+
+        let count: Option<f64> = h.hash_get("count").and_then(|count| count.value().as_f64());
+
+        let args: Vec<(&str, String)> = h
+            .hash()
+            .iter()
+            .filter(|(name, _)| **name != "locale" && **name != "count")
+            .map(|(name, value)| {
+                let rendered = match value.value() {
+                    Json::String(text) => text.clone(),
+                    Json::Null => String::new(),
+                    other => other.to_string(),
+                };
+                (*name, rendered)
+            })
+            .collect();
+
+        out.write(&self.catalog.translate_count(&locale, key, count, &args))?;
+        Ok(())
+    }
+}
+
+/// The text direction of a locale, `ltr` or `rtl`, for `dir` attributes:
+/// `<html dir="{{ dir locale }}">`.
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionHelper;
+
+impl HelperDef for DirectionHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let locale = h
+            .param(0)
+            .and_then(|param| param.value().as_str())
+            .ok_or_else(|| RenderError::new("Param not found for helper \"dir\""))?;
+
+        let direction = match lookup(locale).map(|registered| registered.direction) {
+            Some(TextDirection::Rtl) => "rtl",
+            _ => "ltr",
+        };
+        Ok(ScopedJson::Derived(Json::String(direction.to_string())))
+    }
+}
+
+/// HTML escapes text and isolates quantities such as `1 1/4` so that they
+/// keep their left to right order in right to left pages.
+#[derive(Debug, Clone, Copy)]
+pub struct IsolateHelper;
+
+impl HelperDef for IsolateHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper,
+        _: &Handlebars,
+        _: &Context,
+        _: &mut RenderContext,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let param = h
+            .param(0)
+            .ok_or_else(|| RenderError::new("Param not found for helper \"isolate\""))?;
+
+        out.write(&isolate(param.value().as_str().unwrap_or_default()))?;
+        Ok(())
+    }
+}
+
+fn isolate(src: &str) -> String {
+    let chars: Vec<char> = src.chars().collect();
+    let mut isolated = String::with_capacity(src.len());
+    let mut index = 0;
+
+    while index < chars.len() {
+        if !chars[index].is_ascii_digit() {
+            isolated.push_str(&handlebars::html_escape(&chars[index].to_string()));
+            index += 1;
+            continue;
+        }
+
+        // A quantity is a run of numbers joined by spaces, slashes, dots or
+        // commas. Only quantities with spaces are reordered by the bidi
+        // algorithm, single numbers are left alone.
+        let mut end = index;
+        while end < chars.len() {
+            let joins_next = matches!(chars[end], ' ' | '/' | '.' | ',')
+                && chars.get(end + 1).is_some_and(|c| c.is_ascii_digit());
+            if !chars[end].is_ascii_digit() && !joins_next {
+                break;
+            }
+            end += 1;
+        }
+
+        let quantity: String = chars[index..end].iter().collect();
+        match quantity.contains(' ') {
+            true => write!(&mut isolated, "<bdi dir=\"ltr\">{}</bdi>", quantity).unwrap(),
+            false => isolated.push_str(&quantity),
+        }
+        index = end;
+    }
+    isolated
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct FNVHelper;
 
@@ -64,16 +211,18 @@ impl HelperDef for FNVHelper {
             .param(0)
             .ok_or_else(|| RenderError::new("Param not found for helper \"fnv\""))?;
 
-        let mut fnv_hasher = FnvHasher::default();
-        fnv_hasher.write(param.value().as_str().unwrap().as_bytes());
-
-        let checksum = fnv_hasher.finish();
-
-        out.write(format!("{:x}", checksum).as_str())?;
+        out.write(&fnv(param.value().as_str().unwrap()))?;
         Ok(())
     }
 }
 
+/// The hex FNV hash of a text, used as the anchor of recipe elements.
+pub fn fnv(text: &str) -> String {
+    let mut fnv_hasher = FnvHasher::default();
+    fnv_hasher.write(text.as_bytes());
+    format!("{:x}", fnv_hasher.finish())
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct EscapeHelper;
 
@@ -107,7 +256,7 @@ fn escape(src: &str) -> String {
             '\t' => escaped += "\\t",
             '"' => escaped += "\\\"",
             ' ' => escaped += " ",
-            '\\' => escaped += "\\",
+            '\\' => escaped += "\\\\",
             c if c.is_ascii_graphic() => escaped.push(c),
             c => {
                 let encoded = c.encode_utf16(&mut utf16_buf);
@@ -119,3 +268,29 @@ fn escape(src: &str) -> String {
     }
     escaped
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isolate_ok() {
+        assert_eq!(isolate("2 Tbsp lemon juice"), "2 Tbsp lemon juice");
+        assert_eq!(
+            isolate("1 1/4 cups butter, 10 oz"),
+            "<bdi dir=\"ltr\">1 1/4</bdi> cups butter, 10 oz"
+        );
+        assert_eq!(
+            isolate("Cut 1 1/2\" strips"),
+            "Cut <bdi dir=\"ltr\">1 1/2</bdi>&quot; strips"
+        );
+        assert_eq!(isolate("0.5 lb"), "0.5 lb");
+    }
+
+    #[test]
+    fn escape_ok() {
+        assert_eq!(escape("a \\ b"), "a \\\\ b");
+        assert_eq!(escape("say \"hi\"\n"), "say \\\"hi\\\"\\n");
+        assert_eq!(escape("½"), "\\u00BD");
+    }
+}
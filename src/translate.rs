@@ -0,0 +1,510 @@
+use anyhow::anyhow;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::model::{LocalizedString, Recipe, US_ENGLISH};
+use crate::patch;
+use crate::site::load_recipe_files;
+
+/// A single translatable string from a recipe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranslationUnit {
+    /// `<recipe id>:<yaml path>`, for example
+    /// `681bf47b-d48e-4c42-942b-303c5ed692df:stages[0].steps[1]`.
+    pub context: String,
+    pub source: String,
+    pub target: String,
+    pub fuzzy: bool,
+    pub comments: Vec<String>,
+}
+
+/// The translatable strings of a recipe keyed by their path in the recipe
+/// YAML document.
+pub fn translatable_fields(recipe: &mut Recipe) -> Vec<(String, &mut LocalizedString)> {
+    let Recipe {
+        name,
+        slug,
+        description,
+        keywords,
+        ingredients,
+        equipment,
+        stages,
+        video,
+        ..
+    } = recipe;
+
+    let mut fields: Vec<(String, &mut LocalizedString)> =
+        vec![(String::from("name"), name), (String::from("slug"), slug)];
+    if let Some(description) = description {
+        fields.push((String::from("description"), description));
+    }
+    if let Some(keywords) = keywords {
+        for (index, keyword) in keywords.iter_mut().enumerate() {
+            fields.push((format!("keywords[{}]", index), keyword));
+        }
+    }
+    for (index, ingredient) in ingredients.iter_mut().enumerate() {
+        fields.push((format!("ingredients[{}]", index), &mut ingredient.text));
+    }
+    if let Some(equipment) = equipment {
+        for (index, item) in equipment.iter_mut().enumerate() {
+            fields.push((format!("equipment[{}]", index), item));
+        }
+    }
+    for (stage_index, stage) in stages.iter_mut().enumerate() {
+        fields.push((format!("stages[{}].name", stage_index), &mut stage.name));
+        if let Some(description) = &mut stage.description {
+            fields.push((format!("stages[{}].description", stage_index), description));
+        }
+        if let Some(footer) = &mut stage.footer {
+            fields.push((format!("stages[{}].footer", stage_index), footer));
+        }
+        for (step_index, step) in stage.steps.iter_mut().enumerate() {
+            fields.push((
+                format!("stages[{}].steps[{}]", stage_index, step_index),
+                &mut step.text,
+            ));
+            for (index, item) in step.equipment.iter_mut().enumerate() {
+                fields.push((
+                    format!(
+                        "stages[{}].steps[{}].equipment[{}]",
+                        stage_index, step_index, index
+                    ),
+                    item,
+                ));
+            }
+        }
+    }
+    if let Some(video) = video {
+        fields.push((String::from("video.name"), &mut video.name));
+        if let Some(description) = &mut video.description {
+            fields.push((String::from("video.description"), description));
+        }
+    }
+    fields
+}
+
+/// Write a PO file with one unit per translatable recipe string. Existing
+/// translations are included so they can be reviewed.
+pub fn export_translations(
+    recipe_dir: &Path,
+    locale: &str,
+    output: &Path,
+) -> Result<(), anyhow::Error> {
+    let mut units: Vec<TranslationUnit> = Vec::new();
+
+    for (recipe_file, mut recipe) in load_recipe_files(recipe_dir)? {
+        let recipe_id = recipe.id;
+        let recipe_name = recipe.name.localized(None)?;
+        for (path, value) in translatable_fields(&mut recipe) {
+            units.push(TranslationUnit {
+                context: format!("{}:{}", recipe_id, path),
+                source: value.localized(None)?,
+                target: value.inner.get(locale).cloned().unwrap_or_default(),
+                fuzzy: false,
+                comments: vec![
+                    format!("#. {}", recipe_name),
+                    format!("#: {}", recipe_file.display()),
+                ],
+            });
+        }
+    }
+
+    fs::write(output, write_po(locale, &units))?;
+    println!(
+        "exported {} strings for {} to {}",
+        units.len(),
+        locale,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Merge the translations in PO files into the recipe files, adding the
+/// locale of each file to the locales of every recipe it translates. Only
+/// the translated values and `locales` are rewritten, the rest of each
+/// recipe file is left as it was written.
+pub fn import_translations(recipe_dir: &Path, files: &[PathBuf]) -> Result<(), anyhow::Error> {
+    let mut recipes = load_recipe_files(recipe_dir)?;
+
+    for file in files {
+        let (locale, units) = read_po(&fs::read_to_string(file)?)?;
+        let locale = locale.ok_or_else(|| anyhow!("{} has no Language header", file.display()))?;
+
+        let translations: HashMap<&str, &TranslationUnit> = units
+            .iter()
+            .filter(|unit| !unit.fuzzy && !unit.target.is_empty())
+            .map(|unit| (unit.context.as_str(), unit))
+            .collect();
+
+        for (recipe_file, recipe) in recipes.iter_mut() {
+            let recipe_id = recipe.id.to_string();
+            let mut imported: Vec<String> = Vec::new();
+
+            for (path, value) in translatable_fields(recipe) {
+                let unit = match translations.get(format!("{}:{}", recipe_id, path).as_str()) {
+                    Some(unit) => unit,
+                    None => continue,
+                };
+                if value.inner.get(US_ENGLISH) != Some(&unit.source) {
+                    println!(
+                        "WARNING: {} {} changed since it was exported, skipping",
+                        recipe_file.display(),
+                        path
+                    );
+                    continue;
+                }
+                if value.inner.get(&locale) != Some(&unit.target) {
+                    value.inner.insert(locale.clone(), unit.target.clone());
+                    imported.push(path);
+                }
+            }
+
+            if imported.is_empty() {
+                continue;
+            }
+            let count = imported.len();
+            if !recipe.locales.contains(&locale) {
+                recipe.locales.push(locale.clone());
+                imported.push(String::from("locales"));
+            }
+
+            let value = serde_yaml::to_value(&recipe)?;
+            let mut document = fs::read_to_string(&recipe_file)?;
+            for path in &imported {
+                document = patch::replace(&document, path, patch::value_at(&value, path)?)
+                    .map_err(|err| anyhow!("{}: {}", recipe_file.display(), err))?;
+            }
+            fs::write(&recipe_file, document)?;
+            println!(
+                "imported {} {} strings into {}",
+                count,
+                locale,
+                recipe_file.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// How much of the recipe corpus is translated into a locale.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranslationStatus {
+    pub locale: String,
+    pub translated: usize,
+    pub total: usize,
+    pub percent: f64,
+    pub recipes: Vec<RecipeTranslationStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecipeTranslationStatus {
+    pub id: String,
+    pub file: String,
+    /// Whether the recipe lists the locale in `locales`.
+    pub listed: bool,
+    pub translated: usize,
+    pub total: usize,
+    pub percent: f64,
+    /// The YAML paths of strings that have no translation for the locale.
+    pub missing: Vec<String>,
+}
+
+impl RecipeTranslationStatus {
+    /// A recipe that declares the locale without translating anything is
+    /// published with English content under a translated URL.
+    pub fn listed_untranslated(&self) -> bool {
+        self.listed && self.translated == 0
+    }
+}
+
+/// Report the translation coverage of every recipe for each locale. Only
+/// strings with a value for the locale itself are counted, strings that fall
+/// back to another locale are missing.
+pub fn translation_status(
+    recipe_dir: &Path,
+    locales: &[String],
+) -> Result<Vec<TranslationStatus>, anyhow::Error> {
+    let recipes = load_recipe_files(recipe_dir)?;
+
+    Ok(locales
+        .iter()
+        .map(|locale| {
+            let statuses: Vec<RecipeTranslationStatus> = recipes
+                .iter()
+                .map(|(recipe_file, recipe)| recipe_status(recipe_file, recipe, locale))
+                .collect();
+            let translated = statuses.iter().map(|status| status.translated).sum();
+            let total = statuses.iter().map(|status| status.total).sum();
+            TranslationStatus {
+                locale: locale.clone(),
+                translated,
+                total,
+                percent: percent(translated, total),
+                recipes: statuses,
+            }
+        })
+        .collect())
+}
+
+pub fn recipe_status(recipe_file: &Path, recipe: &Recipe, locale: &str) -> RecipeTranslationStatus {
+    let mut recipe = recipe.clone();
+    let listed = recipe.locales.iter().any(|listed| listed == locale);
+
+    let fields = translatable_fields(&mut recipe);
+    let total = fields.len();
+    let missing: Vec<String> = fields
+        .into_iter()
+        .filter(|(_, value)| !value.inner.contains_key(locale))
+        .map(|(path, _)| path)
+        .collect();
+    let translated = total - missing.len();
+
+    RecipeTranslationStatus {
+        id: recipe.id.to_string(),
+        file: recipe_file.display().to_string(),
+        listed,
+        translated,
+        total,
+        percent: percent(translated, total),
+        missing,
+    }
+}
+
+fn percent(translated: usize, total: usize) -> f64 {
+    match total {
+        0 => 100.0,
+        _ => (translated as f64 * 1000.0 / total as f64).round() / 10.0,
+    }
+}
+
+pub fn write_po(locale: &str, units: &[TranslationUnit]) -> String {
+    let mut po = String::new();
+    po.push_str("msgid \"\"\nmsgstr \"\"\n");
+    po.push_str(&format!("\"Language: {}\\n\"\n", locale));
+    po.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+
+    for unit in units {
+        po.push('\n');
+        for comment in &unit.comments {
+            po.push_str(comment);
+            po.push('\n');
+        }
+        if unit.fuzzy {
+            po.push_str("#, fuzzy\n");
+        }
+        po.push_str(&format!("msgctxt {}\n", quote(&unit.context)));
+        po.push_str(&format!("msgid {}\n", quote(&unit.source)));
+        po.push_str(&format!("msgstr {}\n", quote(&unit.target)));
+    }
+    po
+}
+
+/// Parse a PO file into its `Language` header and translation units.
+pub fn read_po(po: &str) -> Result<(Option<String>, Vec<TranslationUnit>), anyhow::Error> {
+    #[derive(PartialEq)]
+    enum Field {
+        None,
+        Context,
+        Id,
+        Str,
+    }
+
+    let mut language: Option<String> = None;
+    let mut units: Vec<TranslationUnit> = Vec::new();
+    let mut unit = TranslationUnit {
+        context: String::new(),
+        source: String::new(),
+        target: String::new(),
+        fuzzy: false,
+        comments: Vec::new(),
+    };
+    let mut field = Field::None;
+
+    let mut finish = |unit: &mut TranslationUnit, language: &mut Option<String>| {
+        if unit.context.is_empty() && unit.source.is_empty() {
+            for header in unit.target.lines() {
+                if let Some(value) = header.strip_prefix("Language:") {
+                    *language = Some(value.trim().to_string());
+                }
+            }
+        } else {
+            units.push(unit.clone());
+        }
+        *unit = TranslationUnit {
+            context: String::new(),
+            source: String::new(),
+            target: String::new(),
+            fuzzy: false,
+            comments: Vec::new(),
+        };
+    };
+
+    for (line_number, line) in po.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('#') || line.starts_with("msgctxt ") || line.starts_with("msgid ") {
+            // A new unit starts once the previous one has its msgstr.
+            if field == Field::Str {
+                finish(&mut unit, &mut language);
+                field = Field::None;
+            }
+        }
+
+        if let Some(flags) = line.strip_prefix("#,") {
+            unit.fuzzy = flags.split(',').any(|flag| flag.trim() == "fuzzy");
+        } else if line.starts_with('#') {
+            unit.comments.push(line.to_string());
+        } else if let Some(value) = line.strip_prefix("msgctxt ") {
+            unit.context = unquote(value, line_number)?;
+            field = Field::Context;
+        } else if let Some(value) = line.strip_prefix("msgid ") {
+            unit.source = unquote(value, line_number)?;
+            field = Field::Id;
+        } else if let Some(value) = line.strip_prefix("msgstr ") {
+            unit.target = unquote(value, line_number)?;
+            field = Field::Str;
+        } else if line.starts_with('"') {
+            let value = unquote(line, line_number)?;
+            match field {
+                Field::Context => unit.context.push_str(&value),
+                Field::Id => unit.source.push_str(&value),
+                Field::Str => unit.target.push_str(&value),
+                Field::None => {
+                    return Err(anyhow!("unexpected string on line {}", line_number + 1))
+                }
+            }
+        } else {
+            return Err(anyhow!("unexpected content on line {}", line_number + 1));
+        }
+    }
+    if field == Field::Str {
+        finish(&mut unit, &mut language);
+    }
+
+    Ok((language, units))
+}
+
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn unquote(value: &str, line_number: usize) -> Result<String, anyhow::Error> {
+    let inner = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(|| anyhow!("invalid string on line {}", line_number + 1))?;
+
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unquoted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unquoted.push('\n'),
+            Some('t') => unquoted.push('\t'),
+            Some('"') => unquoted.push('"'),
+            Some('\\') => unquoted.push('\\'),
+            _ => return Err(anyhow!("invalid escape on line {}", line_number + 1)),
+        }
+    }
+    Ok(unquoted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn po_round_trip_ok() {
+        let units = vec![
+            TranslationUnit {
+                context: String::from("56b7576b-efb2-4616-b2c4-02e3f381de4e:name"),
+                source: String::from("Wonderful \"food\""),
+                target: String::from("Nourriture \"merveilleuse\""),
+                fuzzy: false,
+                comments: vec![String::from("#. Wonderful food")],
+            },
+            TranslationUnit {
+                context: String::from("56b7576b-efb2-4616-b2c4-02e3f381de4e:stages[0].steps[0]"),
+                source: String::from("First\nthen"),
+                target: String::new(),
+                fuzzy: true,
+                comments: vec![],
+            },
+        ];
+
+        let (language, parsed) = read_po(&write_po("fr_FR", &units)).unwrap();
+        assert_eq!(language, Some(String::from("fr_FR")));
+        assert_eq!(parsed, units);
+    }
+
+    #[test]
+    fn recipe_status_ok() {
+        let mut recipe = Recipe::init(None, None, true);
+        recipe.locales.push(String::from("fr_FR"));
+
+        let status = recipe_status(Path::new("recipe.yml"), &recipe, "fr_FR");
+        assert_eq!(status.total, 11);
+        assert_eq!(status.translated, 0);
+        assert!(status.listed_untranslated());
+        assert!(status.missing.contains(&String::from("stages[0].steps[1]")));
+
+        recipe
+            .name
+            .inner
+            .insert(String::from("fr_FR"), String::from("Une recette"));
+        let status = recipe_status(Path::new("recipe.yml"), &recipe, "fr_FR");
+        assert_eq!(status.translated, 1);
+        assert_eq!(status.percent, 9.1);
+        assert!(!status.listed_untranslated());
+
+        let status = recipe_status(Path::new("recipe.yml"), &recipe, "en_US");
+        assert_eq!(status.percent, 100.0);
+    }
+
+    #[test]
+    fn read_po_multiline_ok() {
+        let (language, units) = read_po(
+            "msgid \"\"
+msgstr \"\"
+\"Language: de_DE\\n\"
+
+msgctxt \"id:name\"
+msgid \"\"
+\"Long \"
+\"name\"
+msgstr \"Langer \"
+\"Name\"
+",
+        )
+        .unwrap();
+        assert_eq!(language, Some(String::from("de_DE")));
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].source, "Long name");
+        assert_eq!(units[0].target, "Langer Name");
+    }
+}
@@ -1,11 +1,39 @@
 use anyhow::{anyhow, Result};
-use std::{collections::HashSet, fs, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
 use uuid::Uuid;
 
-use crate::model::{LocalizedString, Recipe, CATEGORIES, US_ENGLISH};
+use crate::locale;
+use crate::menu::load_menu_files;
+use crate::model::{check_sub_recipes, ImageManifest, LocalizedString, Recipe, US_ENGLISH};
+use crate::nutrition::NutrientTable;
+use crate::schedule::Schedule;
+use crate::site::{load_recipe_files, load_recipes};
+use crate::taxonomy::{Taxonomy, TermKind};
+
+/// Recipe photos with a shorter side than this are rejected.
+const MIN_IMAGE_SIDE: u32 = 600;
+/// Recipe photos narrower than this are too small for large share previews.
+const RECOMMENDED_IMAGE_WIDTH: u32 = 1200;
+/// The largest ratio between the long and short side of a recipe photo.
+const MAX_IMAGE_ASPECT_RATIO: f64 = 2.0;
+const THUMBNAIL_SIZE: u32 = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageKind {
+    Original,
+    Thumbnail,
+    ShareCard(String),
+}
 
 #[cfg(feature = "validate")]
-pub fn validate_recipes(recipe_dir: &Path) -> Result<(), anyhow::Error> {
+pub fn validate_recipes(recipe_dir: &Path, taxonomy_file: &Path) -> Result<(), anyhow::Error> {
+    let taxonomy = Taxonomy::load(taxonomy_file)?;
+    taxonomy.validate()?;
+
     let recipe_files: Vec<String> = walkdir::WalkDir::new(recipe_dir)
         .into_iter()
         .filter_map(|e| e.ok())
@@ -20,8 +48,8 @@ pub fn validate_recipes(recipe_dir: &Path) -> Result<(), anyhow::Error> {
     let mut found_recipe_slugs: HashSet<String> = HashSet::new();
 
     for recipe_file in &recipe_files {
-        let recipe_yaml = fs::read_to_string(&recipe_file)?;
-        let (recipe_id, recipe_slugs) = validate_recipe(recipe_file, &recipe_yaml)?;
+        let recipe_yaml = fs::read_to_string(recipe_file)?;
+        let (recipe_id, recipe_slugs) = validate_recipe(recipe_file, &recipe_yaml, &taxonomy)?;
 
         if found_recipe_ids.contains(&recipe_id) {
             return Err(anyhow!("duplicate id {} in {}", recipe_id, recipe_file));
@@ -37,28 +65,316 @@ pub fn validate_recipes(recipe_dir: &Path) -> Result<(), anyhow::Error> {
 
         println!("OK: {}", recipe_file);
     }
+
+    let recipes = load_recipes(recipe_dir)?;
+    check_sub_recipes(&recipes)?;
+
+    let recipe_locales: HashMap<Uuid, Vec<String>> = recipes
+        .into_iter()
+        .map(|recipe| (recipe.id, recipe.locales))
+        .collect();
+    validate_images(recipe_dir, &recipe_locales)
+}
+
+/// Validate every menu file against the recipes it serves.
+#[cfg(feature = "validate")]
+pub fn validate_menus(menu_dir: &Path, recipe_dir: &Path, taxonomy_file: &Path) -> Result<()> {
+    let taxonomy = Taxonomy::load(taxonomy_file)?;
+    let recipes = load_recipes(recipe_dir)?;
+
+    let mut found_menu_slugs: HashSet<String> = HashSet::new();
+    for (menu_file, menu) in load_menu_files(menu_dir)? {
+        menu.validate(&recipes, &taxonomy)
+            .map_err(|err| anyhow!("{}: {}", menu_file.display(), err))?;
+        for slug in menu.slug.inner.values() {
+            if !found_menu_slugs.insert(slug.clone()) {
+                return Err(anyhow!(
+                    "duplicate slug {} in {}",
+                    slug,
+                    menu_file.display()
+                ));
+            }
+        }
+        println!("OK: {}", menu_file.display());
+    }
+    Ok(())
+}
+
+/// Report the ingredients that are left out of the nutrition facts of each
+/// recipe, so that the nutrient table or the ingredient line can be fixed.
+#[cfg(feature = "validate")]
+pub fn validate_nutrition(recipe_dir: &Path, nutrients_file: &Path) -> Result<()> {
+    let table = NutrientTable::load(nutrients_file)?;
+    let recipe_files = load_recipe_files(recipe_dir)?;
+    let recipes: Vec<Recipe> = recipe_files
+        .iter()
+        .map(|(_, recipe)| recipe.clone())
+        .collect();
+
+    for (recipe_file, recipe) in &recipe_files {
+        let facts = table.recipe_nutrition(recipe, &recipes)?;
+        for (line, reason) in &facts.unmatched {
+            println!(
+                "WARNING: {} nutrition leaves out \"{}\": {}",
+                recipe_file.display(),
+                line,
+                reason
+            );
+        }
+        if recipe.servings.is_none() && recipe.nutrition.is_none() {
+            println!(
+                "WARNING: {} has no nutrition facts because it does not set servings",
+                recipe_file.display()
+            );
+        }
+    }
+    println!("OK: {}", nutrients_file.display());
+    Ok(())
+}
+
+#[cfg(feature = "validate")]
+pub fn validate_images(
+    recipe_dir: &Path,
+    recipe_locales: &HashMap<Uuid, Vec<String>>,
+) -> Result<()> {
+    let manifest = ImageManifest::load(recipe_dir)?;
+
+    let image_files: Vec<String> = walkdir::WalkDir::new(recipe_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.path().display().to_string())
+        .filter(|name| {
+            [".jpg", ".jpeg", ".png", ".gif", ".webp"]
+                .iter()
+                .any(|extension| name.to_lowercase().ends_with(extension))
+        })
+        .collect();
+
+    let mut originals: HashSet<Uuid> = HashSet::new();
+    let mut thumbnails: HashSet<Uuid> = HashSet::new();
+    let mut problems: Vec<String> = Vec::new();
+
+    for image_file in &image_files {
+        if !image_file.ends_with(".jpg") {
+            problems.push(format!("unsupported image format {}", image_file));
+            continue;
+        }
+
+        let file_stem = Path::new(image_file)
+            .file_stem()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+
+        let (recipe_id, kind) = match parse_image_name(file_stem) {
+            Some(parsed) => parsed,
+            None => {
+                problems.push(format!("unrecognized image name {}", image_file));
+                continue;
+            }
+        };
+
+        let locales = match recipe_locales.get(&recipe_id) {
+            Some(locales) => locales,
+            None => {
+                problems.push(format!(
+                    "orphan image {} does not match a recipe id",
+                    image_file
+                ));
+                continue;
+            }
+        };
+
+        let (width, height) = match image::image_dimensions(image_file) {
+            Ok(dimensions) => dimensions,
+            Err(err) => {
+                problems.push(format!("{}: {}", image_file, err));
+                continue;
+            }
+        };
+
+        match kind {
+            ImageKind::Original => {
+                originals.insert(recipe_id);
+                if let Err(err) = validate_image_dimensions(image_file, width, height) {
+                    problems.push(err.to_string());
+                    continue;
+                }
+                if !manifest.images.contains_key(&recipe_id) {
+                    println!(
+                        "WARNING: {} is missing from the image manifest, run `jrb convert`",
+                        image_file
+                    );
+                }
+            }
+            ImageKind::Thumbnail => {
+                if width > THUMBNAIL_SIZE || height > THUMBNAIL_SIZE {
+                    println!(
+                        "WARNING: thumbnail {} is larger than {}x{}",
+                        image_file, THUMBNAIL_SIZE, THUMBNAIL_SIZE
+                    );
+                }
+                thumbnails.insert(recipe_id);
+            }
+            ImageKind::ShareCard(locale) => {
+                if !locales.contains(&locale) {
+                    problems.push(format!(
+                        "share card {} is for locale {} which recipe {} is not published in",
+                        image_file, locale, recipe_id
+                    ));
+                    continue;
+                }
+            }
+        }
+
+        println!("OK: {}", image_file);
+    }
+
+    problems.extend(
+        originals
+            .difference(&thumbnails)
+            .map(|recipe_id| format!("image for recipe {} has no thumbnail", recipe_id)),
+    );
+    problems.extend(
+        thumbnails
+            .difference(&originals)
+            .map(|recipe_id| format!("thumbnail for recipe {} has no image", recipe_id)),
+    );
+    if !problems.is_empty() {
+        problems.sort();
+        return Err(anyhow!(problems.join("\n")));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "validate")]
+pub fn validate_image_dimensions(image_file: &str, width: u32, height: u32) -> Result<()> {
+    let short_side = width.min(height);
+    let long_side = width.max(height);
+
+    if short_side < MIN_IMAGE_SIDE {
+        return Err(anyhow!(
+            "image {} is undersized ({}x{}), the shortest side must be at least {}",
+            image_file,
+            width,
+            height,
+            MIN_IMAGE_SIDE
+        ));
+    }
+
+    if long_side as f64 / short_side as f64 > MAX_IMAGE_ASPECT_RATIO {
+        return Err(anyhow!(
+            "image {} has an aspect ratio wider than {}:1 ({}x{})",
+            image_file,
+            MAX_IMAGE_ASPECT_RATIO,
+            width,
+            height
+        ));
+    }
+
+    if width < RECOMMENDED_IMAGE_WIDTH {
+        println!(
+            "WARNING: {} is low resolution ({}x{}), images should be at least {} wide",
+            image_file, width, height, RECOMMENDED_IMAGE_WIDTH
+        );
+    }
+
     Ok(())
 }
 
+/// Split an image file stem such as `<id>`, `<id>_thumbnail` or
+/// `<id>_<locale>_card` into the recipe id and kind of image.
 #[cfg(feature = "validate")]
-pub fn validate_recipe(recipe_file_name: &str, recipe_yaml: &str) -> Result<(Uuid, Vec<String>)> {
+pub fn parse_image_name(file_stem: &str) -> Option<(Uuid, ImageKind)> {
+    let (id, suffix) = match file_stem.split_once('_') {
+        Some((id, suffix)) => (id, Some(suffix)),
+        None => (file_stem, None),
+    };
+
+    let recipe_id = Uuid::parse_str(id).ok()?;
+
+    let kind = match suffix {
+        None => ImageKind::Original,
+        Some("thumbnail") => ImageKind::Thumbnail,
+        Some(suffix) => ImageKind::ShareCard(suffix.strip_suffix("_card")?.to_string()),
+    };
+
+    Some((recipe_id, kind))
+}
+
+#[cfg(feature = "validate")]
+pub fn validate_recipe(
+    recipe_file_name: &str,
+    recipe_yaml: &str,
+    taxonomy: &Taxonomy,
+) -> Result<(Uuid, Vec<String>)> {
     let deserialized_recipe: Recipe = serde_yaml::from_str(recipe_yaml)?;
 
     if deserialized_recipe.locales.is_empty() {
         return Err(anyhow!("locales cannot be empty"));
     }
+    for locale in &deserialized_recipe.locales {
+        if locale::lookup(locale).is_none() {
+            return Err(anyhow!("unknown locale {}", locale));
+        }
+    }
     if deserialized_recipe.ingredients.is_empty() {
         return Err(anyhow!("ingredients cannot be empty"));
     }
 
-    if !CATEGORIES.contains(&deserialized_recipe.category.localized(None)?.as_str()) {
-        return Err(anyhow!("invalid category"));
+    if taxonomy
+        .term(TermKind::Category, &deserialized_recipe.category)
+        .is_none()
+    {
+        return Err(anyhow!("unknown category {}", deserialized_recipe.category));
+    }
+    if taxonomy
+        .term(TermKind::Cuisine, &deserialized_recipe.cuisine)
+        .is_none()
+    {
+        return Err(anyhow!("unknown cuisine {}", deserialized_recipe.cuisine));
+    }
+
+    let mut anchors: HashSet<String> = HashSet::new();
+    for anchor in deserialized_recipe.anchors() {
+        if anchor.is_empty()
+            || !anchor
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(anyhow!(
+                "id {} must be lowercase letters, digits and hyphens",
+                anchor
+            ));
+        }
+        if !anchors.insert(anchor.clone()) {
+            return Err(anyhow!("duplicate stage or step id {}", anchor));
+        }
     }
 
+    Schedule::new(&deserialized_recipe)?;
+
+    let equipment: Vec<String> = deserialized_recipe
+        .equipment
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|item| item.localized(None).ok())
+        .collect();
+
     validate_localized_string("name", &deserialized_recipe.name)?;
     validate_localized_string("slug", &deserialized_recipe.slug)?;
     validate_optional_localized_string("description", deserialized_recipe.description)?;
-    validate_localized_strings("ingredients", deserialized_recipe.ingredients)?;
+    validate_localized_strings(
+        "ingredients",
+        deserialized_recipe
+            .ingredients
+            .into_iter()
+            .map(|line| line.text)
+            .collect(),
+    )?;
     validate_optional_localized_strings("equipment", deserialized_recipe.equipment)?;
 
     if deserialized_recipe.stages.is_empty() {
@@ -80,7 +396,26 @@ pub fn validate_recipe(recipe_file_name: &str, recipe_yaml: &str) -> Result<(Uui
         validate_localized_string("stage.name", &stage.name)?;
         validate_optional_localized_string("stage.description", stage.description)?;
         validate_optional_localized_string("stage.footer", stage.footer)?;
-        validate_localized_strings("stage.steps", stage.steps)?;
+        for step in &stage.steps {
+            for item in &step.equipment {
+                let name = item.localized(None)?;
+                if !equipment.contains(&name) {
+                    return Err(anyhow!("step equipment {} is not in equipment", name));
+                }
+            }
+        }
+        validate_localized_strings(
+            "stage.steps",
+            stage.steps.iter().map(|step| step.text.clone()).collect(),
+        )?;
+        validate_localized_strings(
+            "step.equipment",
+            stage
+                .steps
+                .into_iter()
+                .flat_map(|step| step.equipment)
+                .collect(),
+        )?;
     }
 
     if step_count > 20 {
@@ -108,8 +443,8 @@ pub fn validate_localized_strings(key: &str, values: Vec<LocalizedString>) -> Re
 
 #[cfg(feature = "validate")]
 pub fn validate_optional_localized_string(key: &str, value: Option<LocalizedString>) -> Result<()> {
-    if value.is_some() {
-        validate_localized_string(key, &value.unwrap())?;
+    if let Some(value) = value {
+        validate_localized_string(key, &value)?;
     }
     Ok(())
 }
@@ -119,8 +454,8 @@ pub fn validate_optional_localized_strings(
     key: &str,
     values: Option<Vec<LocalizedString>>,
 ) -> Result<()> {
-    if values.is_some() {
-        for value in values.unwrap() {
+    if let Some(values) = values {
+        for value in values {
             validate_localized_string(key, &value)?;
         }
     }
@@ -131,13 +466,107 @@ pub fn validate_optional_localized_strings(
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_image_name_ok() {
+        let recipe_id = Uuid::parse_str("681bf47b-d48e-4c42-942b-303c5ed692df").unwrap();
+
+        assert_eq!(
+            parse_image_name("681bf47b-d48e-4c42-942b-303c5ed692df"),
+            Some((recipe_id, ImageKind::Original))
+        );
+        assert_eq!(
+            parse_image_name("681bf47b-d48e-4c42-942b-303c5ed692df_thumbnail"),
+            Some((recipe_id, ImageKind::Thumbnail))
+        );
+        assert_eq!(
+            parse_image_name("681bf47b-d48e-4c42-942b-303c5ed692df_en_US_card"),
+            Some((recipe_id, ImageKind::ShareCard(String::from("en_US"))))
+        );
+        assert_eq!(
+            parse_image_name("681bf47b-d48e-4c42-942b-303c5ed692df_large"),
+            None
+        );
+        assert_eq!(parse_image_name("jambalaya"), None);
+    }
+
+    #[test]
+    fn validate_image_dimensions_err() {
+        assert!(validate_image_dimensions("ok.jpg", 1536, 2048).is_ok());
+        assert_eq!(
+            validate_image_dimensions("small.jpg", 400, 300)
+                .unwrap_err()
+                .to_string(),
+            "image small.jpg is undersized (400x300), the shortest side must be at least 600"
+        );
+        assert_eq!(
+            validate_image_dimensions("wide.jpg", 3000, 1000)
+                .unwrap_err()
+                .to_string(),
+            "image wide.jpg has an aspect ratio wider than 2:1 (3000x1000)"
+        );
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn validate_images_err() {
+        let recipe_dir = std::env::temp_dir().join(format!("jrb-validate-{}", Uuid::new_v4()));
+        fs::create_dir_all(&recipe_dir).unwrap();
+        let recipe_id = Uuid::new_v4();
+        let orphan_id = Uuid::new_v4();
+        let small = image::RgbImage::new(100, 100);
+        small
+            .save(recipe_dir.join(format!("{}.jpg", recipe_id)))
+            .unwrap();
+        small
+            .save(recipe_dir.join(format!("{}_fr_FR_card.jpg", recipe_id)))
+            .unwrap();
+        fs::write(recipe_dir.join("cover.png"), "").unwrap();
+        fs::write(recipe_dir.join("cover.jpg"), "").unwrap();
+        fs::write(recipe_dir.join(format!("{}.jpg", orphan_id)), "").unwrap();
+
+        let recipe_locales = HashMap::from([(recipe_id, vec![String::from("en_US")])]);
+        let res = validate_images(&recipe_dir, &recipe_locales);
+        fs::remove_dir_all(&recipe_dir).unwrap();
+
+        let dir = recipe_dir.display();
+        let mut expected = [
+            format!("unsupported image format {}/cover.png", dir),
+            format!("unrecognized image name {}/cover.jpg", dir),
+            format!(
+                "orphan image {}/{}.jpg does not match a recipe id",
+                dir, orphan_id
+            ),
+            format!(
+                "image {}/{}.jpg is undersized (100x100), the shortest side must be at least 600",
+                dir, recipe_id
+            ),
+            format!(
+                "share card {}/{}_fr_FR_card.jpg is for locale fr_FR which recipe {} is not published in",
+                dir, recipe_id, recipe_id
+            ),
+            format!("image for recipe {} has no thumbnail", recipe_id),
+        ];
+        expected.sort();
+        assert_eq!(res.unwrap_err().to_string(), expected.join("\n"));
+    }
+
+    const TAXONOMY: &str = "
+categories:
+  - id: main-dish
+    label: Main dish
+cuisines:
+  - id: american
+    label: American
+";
+
     macro_rules! validate_recipe_parse_tests {
         ($($name:ident: $value:expr,)*) => {
         $(
             #[test]
             fn $name() {
                 let (input, expected) = $value;
-                let res = validate_recipe("$name", input);
+                let taxonomy: Taxonomy = serde_yaml::from_str(TAXONOMY).unwrap();
+                let res = validate_recipe("$name", input, &taxonomy);
                 assert!(res.is_err());
                 assert_eq!(res.unwrap_err().to_string(), expected);
             }
@@ -165,7 +594,7 @@ name: wonderful food", "missing field `slug` at line 2 column 3"),
 id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
 locales: []
 name: wonderful food
-category: main dish
+category: main-dish
 cuisine: american
 slug: 02e3f381de4e-wonderful-food", "missing field `published` at line 2 column 3"),
 
@@ -174,7 +603,7 @@ id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
 locales: []
 published: 2022-01-01
 name: wonderful food
-category: main dish
+category: main-dish
 cuisine: american
 slug: 02e3f381de4e-wonderful-food", "missing field `ingredients` at line 2 column 3"),
 
@@ -192,7 +621,7 @@ locales: []
 published: 2022-01-01
 name: wonderful food
 slug: 02e3f381de4e-wonderful-food
-category: main dish
+category: main-dish
 ingredients: []", "missing field `cuisine` at line 2 column 3"),
 
     //         validate_recipe_err_missing_equipment: ("---
@@ -201,7 +630,7 @@ ingredients: []", "missing field `cuisine` at line 2 column 3"),
     // published: 2022-01-01
     // name: wonderful food
     // slug: 02e3f381de4e-wonderful-food
-    // category: main dish
+    // category: main-dish
     // cuisine: american
     // ingredients: []", "missing field `equipment` at line 2 column 3"),
 
@@ -212,7 +641,7 @@ published: 2022-01-01
 name: wonderful food
 slug: 02e3f381de4e-wonderful-food
 ingredients: []
-category: main dish
+category: main-dish
 cuisine: american
 equipment: []", "missing field `stages` at line 2 column 3"),
             validate_recipe_err_empty_locales: ("---
@@ -221,23 +650,34 @@ locales: []
 published: 2022-01-01
 name: wonderful food
 slug: 02e3f381de4e-wonderful-food
-category: main dish
+category: main-dish
 cuisine: american
 ingredients: []
 equipment: []
 stages: []", "locales cannot be empty"),
+            validate_recipe_err_unknown_locale: ("---
+id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
+locales: [en_US, xx_XX]
+published: 2022-01-01
+name: wonderful food
+slug: 02e3f381de4e-wonderful-food
+category: main-dish
+cuisine: american
+ingredients: []
+equipment: []
+stages: []", "unknown locale xx_XX"),
             validate_recipe_err_empty_ingredients: ("---
 id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
 locales: [en_US]
 published: 2022-01-01
 name: wonderful food
-category: main dish
+category: main-dish
 cuisine: american
 slug: 02e3f381de4e-wonderful-food
 ingredients: []
 equipment: []
 stages: []", "ingredients cannot be empty"),
-            validate_recipe_err_empty_stages: ("---
+            validate_recipe_err_unknown_category: ("---
 id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
 locales: [en_US]
 published: 2022-01-01
@@ -247,13 +687,35 @@ cuisine: american
 slug: 02e3f381de4e-wonderful-food
 ingredients: [food_a]
 equipment: []
+stages: []", "unknown category main dish"),
+            validate_recipe_err_unknown_cuisine: ("---
+id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
+locales: [en_US]
+published: 2022-01-01
+name: wonderful food
+category: main-dish
+cuisine: cajun
+slug: 02e3f381de4e-wonderful-food
+ingredients: [food_a]
+equipment: []
+stages: []", "unknown cuisine cajun"),
+            validate_recipe_err_empty_stages: ("---
+id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
+locales: [en_US]
+published: 2022-01-01
+name: wonderful food
+category: main-dish
+cuisine: american
+slug: 02e3f381de4e-wonderful-food
+ingredients: [food_a]
+equipment: []
 stages: []", "stages cannot be empty"),
             validate_recipe_err_missing_steps: ("---
 id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
 locales: [en_US]
 published: 2022-01-01
 name: wonderful food
-category: main dish
+category: main-dish
 cuisine: american
 slug: 02e3f381de4e-wonderful-food
 ingredients: [food_a]
@@ -265,7 +727,7 @@ id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
 locales: [en_US]
 published: 2022-01-01
 name: wonderful food
-category: main dish
+category: main-dish
 cuisine: american
 slug: 02e3f381de4e-wonderful-food
 ingredients: [food_a]
@@ -280,7 +742,7 @@ published: 2022-01-01
 name:
   en_GB: wonderful food
 slug: 02e3f381de4e-wonderful-food
-category: main dish
+category: main-dish
 cuisine: american
 ingredients: [food_a]
 equipment: []
@@ -294,7 +756,7 @@ locales: [en_US]
 published: 2022-01-01
 name: wonderful food
 slug: 02e3f381de4e-wonderful-food
-category: main dish
+category: main-dish
 cuisine: american
 ingredients: [food_a]
 equipment: []
@@ -303,13 +765,100 @@ stages:
   steps:
   - en_GB: first
 ", "stage.steps must have en_US translation"),
+            validate_recipe_err_step_equipment: ("---
+id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
+locales: [en_US]
+published: 2022-01-01
+name: wonderful food
+slug: 02e3f381de4e-wonderful-food
+category: main-dish
+cuisine: american
+ingredients: [food_a]
+equipment: [pan]
+stages:
+- name: prep
+  steps:
+  - text: Bake for 20 minutes.
+    duration: 20m
+    temperature: 350F
+    equipment: [oven]
+", "step equipment oven is not in equipment"),
+            validate_recipe_err_step_temperature: ("---
+id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
+locales: [en_US]
+published: 2022-01-01
+name: wonderful food
+slug: 02e3f381de4e-wonderful-food
+category: main-dish
+cuisine: american
+ingredients: [food_a]
+equipment: [pan]
+stages:
+- name: prep
+  steps:
+  - text: Bake.
+    temperature: hot
+", "stages[0].steps[0]: hot: the temperature must end in F or C at line 14 column 9"),
+            validate_recipe_err_stage_after: ("---
+id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
+locales: [en_US]
+published: 2022-01-01
+name: wonderful food
+slug: 02e3f381de4e-wonderful-food
+category: main-dish
+cuisine: american
+ingredients: [food_a]
+equipment: []
+stages:
+- name: prep
+  after: [bake]
+  steps:
+  - first
+- id: bake
+  name: bake
+  steps:
+  - second
+", "stage stage-1 is after bake, which is not an earlier stage"),
+            validate_recipe_err_duplicate_step_id: ("---
+id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
+locales: [en_US]
+published: 2022-01-01
+name: wonderful food
+slug: 02e3f381de4e-wonderful-food
+category: main-dish
+cuisine: american
+ingredients: [food_a]
+equipment: []
+stages:
+- name: prep
+  steps:
+  - first
+  - id: stage-1-step-1
+    text: second
+", "duplicate stage or step id stage-1-step-1"),
+            validate_recipe_err_invalid_stage_id: ("---
+id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
+locales: [en_US]
+published: 2022-01-01
+name: wonderful food
+slug: 02e3f381de4e-wonderful-food
+category: main-dish
+cuisine: american
+ingredients: [food_a]
+equipment: []
+stages:
+- id: Make Sauce
+  name: prep
+  steps:
+  - first
+", "id Make Sauce must be lowercase letters, digits and hyphens"),
             validate_recipe_err_stage_invalid_duration: ("---
 id: 56b7576b-efb2-4616-b2c4-02e3f381de4e
 locales: [en_US]
 published: 2022-01-01
 name: wonderful food
 slug: 02e3f381de4e-wonderful-food
-category: main dish
+category: main-dish
 cuisine: american
 ingredients: [food_a]
 equipment: []
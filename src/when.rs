@@ -8,7 +8,7 @@ const SECONDS_IN_WEEK: u64 = 604800;
 // NG: I'm sure there are better ways to do this.
 pub fn duration_iso8601(duration: Duration) -> String {
     if duration.is_zero() {
-        return String::from("P");
+        return String::from("PT0S");
     }
 
     let mut duration_parts: Vec<String> = vec![String::from("P")];
@@ -24,7 +24,7 @@ pub fn duration_iso8601(duration: Duration) -> String {
         duration_parts.push(format!("{}D", days));
         seconds -= days * SECONDS_IN_DAY;
     }
-    if duration_parts.len() > 1 && seconds > 0 {
+    if seconds > 0 {
         duration_parts.push(String::from("T"));
     }
     if seconds >= SECONDS_IN_HOUR {
@@ -44,6 +44,46 @@ pub fn duration_iso8601(duration: Duration) -> String {
     duration_parts.join("")
 }
 
+/// Parse an ISO 8601 duration of weeks, days, hours, minutes and seconds,
+/// such as `PT1H10M`. Years and months are rejected as they have no fixed
+/// length.
+pub fn parse_duration_iso8601(src: &str) -> Option<Duration> {
+    let mut chars = src.strip_prefix('P')?.chars().peekable();
+    let mut seconds: u64 = 0;
+    let mut components = 0;
+    let mut in_time = false;
+    let mut number = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' if !in_time && number.is_empty() => {
+                in_time = true;
+                chars.peek()?;
+            }
+            designator => {
+                let value: u64 = number.parse().ok()?;
+                number.clear();
+                seconds += value
+                    * match (in_time, designator) {
+                        (false, 'W') => SECONDS_IN_WEEK,
+                        (false, 'D') => SECONDS_IN_DAY,
+                        (true, 'H') => SECONDS_IN_HOUR,
+                        (true, 'M') => SECONDS_IN_MINUTE,
+                        (true, 'S') => 1,
+                        _ => return None,
+                    };
+                components += 1;
+            }
+        }
+    }
+
+    match number.is_empty() && components > 0 {
+        true => Some(Duration::from_secs(seconds)),
+        false => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,19 +91,42 @@ mod tests {
 
     #[test]
     fn fmt_duration_ok() {
-        assert_eq!(duration_iso8601(Duration::new(300, 0)), "P5M");
+        assert_eq!(duration_iso8601(Duration::new(300, 0)), "PT5M");
 
         assert_eq!(
             duration_iso8601(parse_duration("2h 37min").unwrap()),
-            "P2H37M"
+            "PT2H37M"
         );
         assert_eq!(
             duration_iso8601(parse_duration("30 minutes").unwrap()),
-            "P30M"
+            "PT30M"
         );
         assert_eq!(
             duration_iso8601(parse_duration("70 minutes").unwrap()),
-            "P1H10M"
+            "PT1H10M"
+        );
+        assert_eq!(
+            duration_iso8601(parse_duration("1 day 2h").unwrap()),
+            "P1DT2H"
+        );
+    }
+
+    #[test]
+    fn parse_duration_ok() {
+        assert_eq!(parse_duration_iso8601("PT5M"), Some(Duration::new(300, 0)));
+        assert_eq!(parse_duration_iso8601("PT0S"), Some(Duration::ZERO));
+        assert_eq!(
+            parse_duration_iso8601("P1DT1H10M"),
+            Some(Duration::new(86400 + 4200, 0))
+        );
+        assert_eq!(
+            parse_duration_iso8601("P1W"),
+            Some(Duration::new(604800, 0))
         );
+        assert_eq!(parse_duration_iso8601("P5M"), None);
+        assert_eq!(parse_duration_iso8601("PT"), None);
+        assert_eq!(parse_duration_iso8601("P"), None);
+        assert_eq!(parse_duration_iso8601("PT5"), None);
+        assert_eq!(parse_duration_iso8601("5 minutes"), None);
     }
 }